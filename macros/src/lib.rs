@@ -0,0 +1,259 @@
+//! Procedural macros for [bevy_testing](https://docs.rs/bevy_testing).
+//!
+//! Use them through the re-exports in `bevy_testing`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    meta::ParseNestedMeta, parse_macro_input, punctuated::Punctuated, Expr, ExprArray, FnArg,
+    ItemFn, LitStr, Token,
+};
+
+/// Turns a function taking `&mut App` into a test, which builds an app, runs it and passes it
+/// to the function.
+///
+/// The function gets called with the app after it was updated `frames` times.
+/// If the test fails, the name of the test and the amount of frames are printed.
+///
+/// Arguments, all of which are optional:
+///
+/// argument                   | description
+/// ---------------------------|--
+/// `plugins = [A, B]`         | plugins which get added to the app
+/// `frames = 10`              | the amount of updates to run before calling the function
+/// `should_panic`             | the test passes if it panics
+/// `should_panic = "message"` | the test passes if it panics with a message containing `"message"`
+///
+/// To run the function once per set of parameters, add `#[case(..)]` attributes *below*
+/// `#[bevy_test]`. The parameters are passed after the app, and each case becomes its own test
+/// named `case_1`, `case_2`, ... in a module named after the function.
+///
+/// ```
+/// use bevy_testing::p::*;
+/// use bevy_testing::bevy_test;
+///
+/// #[derive(Component, Debug, PartialEq)]
+/// struct Countdown(u32);
+///
+/// struct CountdownPlugin;
+///
+/// impl Plugin for CountdownPlugin {
+///     fn build(&self, app: &mut App) {
+///         app.world_mut().spawn(Countdown(10));
+///         app.add_systems(Update, |mut query: Query<&mut Countdown>| {
+///             for mut countdown in &mut query {
+///                 countdown.0 -= 1;
+///             }
+///         });
+///     }
+/// }
+///
+/// #[bevy_test(plugins = [CountdownPlugin], frames = 3)]
+/// fn counts_down(app: &mut App) {
+///     app.query::<&Countdown>()
+///         .matches(vec![&Countdown(7)]);
+/// }
+///
+/// #[bevy_test(plugins = [CountdownPlugin], should_panic)]
+/// fn counts_past_zero(app: &mut App) {
+///     app.update_n_times(11);
+/// }
+///
+/// #[bevy_test(plugins = [CountdownPlugin])]
+/// #[case(1, 9)]
+/// #[case(4, 6)]
+/// fn counts_down_by_frames(app: &mut App, frames: u32, left: u32) {
+///     app.update_n_times(frames);
+///     app.query::<&Countdown>()
+///         .matches(vec![&Countdown(left)]);
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn bevy_test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut args = Args::default();
+    let parser = syn::meta::parser(|meta| args.parse(meta));
+    parse_macro_input!(attr with parser);
+    let item = parse_macro_input!(item as ItemFn);
+
+    expand(args, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct Args {
+    plugins: Vec<Expr>,
+    frames: Option<Expr>,
+    should_panic: Option<Option<LitStr>>,
+}
+
+impl Args {
+    fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("plugins") {
+            let plugins: ExprArray = meta.value()?.parse()?;
+            self.plugins = plugins.elems.into_iter().collect();
+        } else if meta.path.is_ident("frames") {
+            self.frames = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("should_panic") {
+            let expected = if meta.input.peek(Token![=]) {
+                Some(meta.value()?.parse()?)
+            } else {
+                None
+            };
+            self.should_panic = Some(expected);
+        } else {
+            return Err(meta.error("expected `plugins`, `frames` or `should_panic`"));
+        }
+        Ok(())
+    }
+
+    fn should_panic(&self) -> TokenStream2 {
+        match &self.should_panic {
+            Some(Some(expected)) => quote!(#[should_panic(expected = #expected)]),
+            Some(None) => quote!(#[should_panic]),
+            None => quote!(),
+        }
+    }
+
+    /// A test function which builds the app and calls `call` with it.
+    fn test(
+        &self,
+        name: &syn::Ident,
+        label: &str,
+        items: TokenStream2,
+        call: TokenStream2,
+    ) -> TokenStream2 {
+        let should_panic = self.should_panic();
+        let body = self.body(label, items, call);
+
+        quote! {
+            #[test]
+            #should_panic
+            fn #name() {
+                #body
+            }
+        }
+    }
+
+    /// Registers a test like [`Self::test`] with the `bevy_testing` harness, which runs it in
+    /// `harness = false` targets, where `#[test]` functions are ignored.
+    fn register(&self, label: &str, items: TokenStream2, call: TokenStream2) -> TokenStream2 {
+        let body = self.body(label, items, call);
+        let panics = self.should_panic.is_some();
+        let expected = match &self.should_panic {
+            Some(Some(expected)) => quote!(::core::option::Option::Some(#expected)),
+            _ => quote!(::core::option::Option::None),
+        };
+
+        quote! {
+            ::bevy_testing::__register_test! {
+                name: ::core::concat!(::core::module_path!(), "::", #label),
+                should_panic: #panics,
+                expected: #expected,
+                run: {
+                    fn run() {
+                        #body
+                    }
+                    run
+                },
+            }
+        }
+    }
+
+    /// Statements which build the app and call `call` with it.
+    fn body(&self, label: &str, items: TokenStream2, call: TokenStream2) -> TokenStream2 {
+        let plugins = &self.plugins;
+        let add_plugins = (!plugins.is_empty()).then(|| quote!(app.add_plugins((#(#plugins,)*));));
+        let frames = match &self.frames {
+            Some(frames) => quote!(#frames),
+            None => quote!(0),
+        };
+
+        quote! {
+            #items
+            let mut app = ::bevy_testing::p::App::new();
+            #add_plugins
+            ::bevy_testing::__private::run_test(#label, #frames, app, #call);
+        }
+    }
+}
+
+fn expand(args: Args, mut item: ItemFn) -> syn::Result<TokenStream2> {
+    if !matches!(item.sig.inputs.first(), Some(FnArg::Typed(_))) {
+        return Err(syn::Error::new_spanned(
+            &item.sig,
+            "a `#[bevy_test]` function needs to take `&mut App` as its first argument",
+        ));
+    }
+
+    let mut cases = Vec::new();
+    let mut attrs = Vec::new();
+    for attr in item.attrs.drain(..) {
+        if attr.path().is_ident("case") {
+            let values = attr.parse_args_with(Punctuated::<Expr, Token![,]>::parse_terminated)?;
+            cases.push(values.into_iter().collect::<Vec<_>>());
+        } else {
+            attrs.push(attr);
+        }
+    }
+    item.attrs = attrs;
+
+    let name = item.sig.ident.clone();
+    let params = item.sig.inputs.len() - 1;
+    if cases.is_empty() {
+        if params != 0 {
+            return Err(syn::Error::new_spanned(
+                &item.sig.inputs,
+                "parameters after the app need to be given via `#[case(..)]`",
+            ));
+        }
+        // the function is nested inside the test, which keeps its name free
+        let test = args.test(&name, &name.to_string(), quote!(#item), quote!(#name));
+        let register = args.register(&name.to_string(), quote!(#item), quote!(#name));
+        return Ok(quote!(#test #register));
+    }
+
+    let mut tests = Vec::new();
+    let mut registers = Vec::new();
+    for (i, case) in cases.iter().enumerate() {
+        if case.len() != params {
+            return Err(syn::Error::new_spanned(
+                &item.sig.inputs,
+                format!(
+                    "case {} has {} parameters, but the function takes {params} after the app",
+                    i + 1,
+                    case.len()
+                ),
+            ));
+        }
+        let test_name = format_ident!("case_{}", i + 1);
+        let label = format!("{name}::{test_name}");
+        tests.push(args.test(
+            &test_name,
+            &label,
+            quote!(),
+            quote!(|app: &mut ::bevy_testing::p::App| super::#name(app, #(#case),*)),
+        ));
+        registers.push(args.register(
+            &label,
+            quote!(),
+            quote!(|app: &mut ::bevy_testing::p::App| #name(app, #(#case),*)),
+        ));
+    }
+
+    Ok(quote! {
+        #[cfg(test)]
+        #item
+
+        #[cfg(test)]
+        mod #name {
+            #[allow(unused_imports)]
+            use super::*;
+
+            #(#tests)*
+        }
+
+        #(#registers)*
+    })
+}
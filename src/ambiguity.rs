@@ -0,0 +1,102 @@
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use std::any::{type_name, TypeId};
+
+use bevy::{
+    ecs::schedule::{InternedScheduleLabel, LogLevel, ScheduleBuildSettings},
+    utils::get_short_name,
+};
+use colored::Colorize;
+
+use crate::print_failure;
+
+/// The pairs of systems which may run in any order, as allowed via [`App::allow_ambiguity`].
+#[derive(Resource, Default)]
+pub(crate) struct AllowedAmbiguities(pub(crate) Vec<(TypeId, TypeId)>);
+
+impl AllowedAmbiguities {
+    fn contains(&self, a: TypeId, b: TypeId) -> bool {
+        self.0.iter().any(|&pair| pair == (a, b) || pair == (b, a))
+    }
+}
+
+/// Builds the schedule and panics if any systems with conflicting data access are unordered.
+pub(crate) fn assert_no_ambiguities(world: &mut World, label: InternedScheduleLabel) {
+    world
+        .try_schedule_scope(label, |world, schedule| {
+            // an `Error` level would abort the build before the conflicts are stored
+            let settings = schedule.get_build_settings();
+            schedule.set_build_settings(ScheduleBuildSettings {
+                ambiguity_detection: LogLevel::Ignore,
+                ..settings.clone()
+            });
+            let result = schedule.initialize(world);
+            schedule.set_build_settings(settings);
+            result
+        })
+        .unwrap_or_else(|_| panic!("the schedule {label:?} doesn't exist"))
+        .unwrap_or_else(|err| panic!("the schedule {label:?} can't be built: {err}"));
+
+    let schedule = world.resource::<Schedules>().get(label).unwrap();
+    let allowed = world.get_resource::<AllowedAmbiguities>();
+    let systems = schedule
+        .systems()
+        .expect("the schedule was initialized")
+        .map(|(id, system)| (id, (system.type_id(), get_short_name(&system.name()))))
+        .collect::<Vec<_>>();
+    let system = |id| {
+        systems
+            .iter()
+            .find(|(other, _)| *other == id)
+            .map(|(_, system)| system)
+            .unwrap()
+    };
+
+    let mut allowed_count = 0;
+    let mut conflicts = Vec::new();
+    for (a, b, components) in schedule.graph().conflicting_systems() {
+        let ((a_type, a_name), (b_type, b_name)) = (system(*a), system(*b));
+        if allowed.is_some_and(|allowed| allowed.contains(*a_type, *b_type)) {
+            allowed_count += 1;
+            continue;
+        }
+        let data = if components.is_empty() {
+            // one of the systems is exclusive
+            vec![format!(
+                "{} (exclusive)",
+                get_short_name(type_name::<World>())
+            )]
+        } else {
+            components
+                .iter()
+                .map(|id| get_short_name(world.components().get_name(*id).unwrap()))
+                .collect()
+        };
+        conflicts.push((a_name, b_name, data));
+    }
+
+    if conflicts.is_empty() {
+        return;
+    }
+    print_failure(&format!(
+        "The schedule {label:?} contains {} ambiguities.",
+        conflicts.len()
+    ));
+    for (a, b, data) in conflicts {
+        eprintln!("  {a} and {b}");
+        eprintln!("    {} {}", "conflict on:".bright_black(), data.join(", "));
+    }
+    if allowed_count > 0 {
+        eprintln!(
+            "{}",
+            format!("Ignored allowed ambiguities: {allowed_count}").bright_black()
+        );
+    }
+    eprintln!(
+        "{}",
+        "Order the systems via `before`, `after` or `chain`, or allow the ambiguity."
+            .bright_black()
+    );
+    panic!("assertion failed");
+}
@@ -0,0 +1,229 @@
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use std::{
+    any::{type_name, TypeId},
+    collections::{HashMap, HashSet},
+    marker::PhantomData,
+};
+
+use bevy::ecs::component::Tick;
+
+use crate::{mismatch, unexpected_match};
+
+/// Whether an [`AssertChanged`] looks for changed or for added components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum ChangeKind {
+    Changed,
+    Added,
+}
+
+impl ChangeKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ChangeKind::Changed => "changed",
+            ChangeKind::Added => "added",
+        }
+    }
+}
+
+/// A resource which stores the tick of the last change detection assertion per component.
+#[derive(Resource, Default)]
+pub(crate) struct ChangeBaselines {
+    ticks: HashMap<(TypeId, ChangeKind), Tick>,
+}
+
+/// Collects all entities whose component `T` was changed or added since the last assertion of
+/// the same kind, and moves the baseline for the next assertion to now.
+pub(crate) fn collect_changes<T: Component>(world: &mut World, kind: ChangeKind) -> Vec<Entity> {
+    let key = (TypeId::of::<T>(), kind);
+    let last_run = world
+        .get_resource::<ChangeBaselines>()
+        .and_then(|baselines| baselines.ticks.get(&key).copied())
+        .unwrap_or(Tick::new(0));
+    let this_run = world.read_change_tick();
+
+    let mut query = world.query_filtered::<Entity, With<T>>();
+    let entities = query
+        .iter(world)
+        .filter(|entity| {
+            let ticks = world.entity(*entity).get_change_ticks::<T>().unwrap();
+            match kind {
+                ChangeKind::Changed => ticks.is_changed(last_run, this_run),
+                ChangeKind::Added => ticks.is_added(last_run, this_run),
+            }
+        })
+        .collect();
+
+    // changes made after this point must be newer than the baseline
+    let baseline = world.increment_change_tick();
+    world
+        .get_resource_or_insert_with(ChangeBaselines::default)
+        .ticks
+        .insert(key, baseline);
+
+    entities
+}
+
+/// A struct to perform tests on change detection which is created via [`App::changed`] or [`App::added`].
+///
+/// ```
+/// use bevy_testing::p::*;
+///
+/// #[derive(Component)]
+/// struct Health(u32);
+///
+/// fn regenerate(mut query: Query<&mut Health>) {
+///     for mut health in &mut query {
+///         if health.0 < 10 {
+///             health.0 += 1;
+///         }
+///     }
+/// }
+///
+/// let mut app = App::new();
+/// app.add_systems(Update, regenerate);
+/// let hurt = app.spawn(Health(5)).id();
+/// app.spawn(Health(10));
+/// app.changed::<Health>(); // everything spawned counts as changed
+///
+/// app.update_once();
+/// app.changed::<Health>()
+///     .only(hurt);
+/// ```
+pub struct AssertChanged<T: Component> {
+    pub(crate) entities: Vec<Entity>,
+    pub(crate) kind: ChangeKind,
+    pub(crate) invert: bool,
+    pub(crate) marker: PhantomData<T>,
+}
+
+impl<T: Component> AssertChanged<T> {
+    /// Returns an inverted [`AssertChanged`].
+    /// When chaining methods,
+    /// the inverted state gets reset after every method.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.spawn(Health(5));
+    ///
+    /// app.changed::<Health>()
+    ///     .not().none();
+    /// ```
+    #[allow(clippy::should_implement_trait)] // users should not need to import std::ops::Not
+    pub fn not(mut self) -> Self {
+        self.invert = !self.invert;
+        self
+    }
+
+    /// Checks if the changed entities are the given and only the given entities.
+    /// The given entities do not need to be in order.
+    ///
+    /// This can be inverted via [`Self::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut app = App::new();
+    /// let a = app.spawn(Health(5)).id();
+    /// let b = app.spawn(Health(5)).id();
+    ///
+    /// app.changed::<Health>()
+    ///     .entities_eq(vec![b, a])
+    ///     .not().entities_eq(vec![a]);
+    ///
+    /// app.entity_mut(a).get_mut::<Health>().unwrap().0 = 4;
+    /// app.entity_mut(b).get_mut::<Health>().unwrap().0 = 4;
+    /// app.changed::<Health>()
+    ///     .not().entities_eq(vec![a, a]);
+    /// ```
+    pub fn entities_eq(self, given: Vec<Entity>) -> Self {
+        let is_match =
+            given.iter().collect::<HashSet<_>>() == self.entities.iter().collect::<HashSet<_>>();
+        if self.invert {
+            if is_match {
+                unexpected_match(
+                    &format!(
+                        "The {} entities of \"{}\" match the given entities.",
+                        self.kind.as_str(),
+                        type_name::<T>()
+                    ),
+                    given,
+                );
+            }
+            return self.reset_invert();
+        }
+
+        if !is_match {
+            mismatch(
+                &format!(
+                    "The {} entities of \"{}\" mismatch.",
+                    self.kind.as_str(),
+                    type_name::<T>()
+                ),
+                given,
+                &self.entities,
+            );
+        }
+
+        self
+    }
+
+    /// Checks if no entity has a changed component.
+    ///
+    /// This can be inverted via [`Self::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.spawn(Health(5));
+    /// app.changed::<Health>();
+    ///
+    /// app.update_once();
+    /// app.changed::<Health>()
+    ///     .none();
+    /// ```
+    pub fn none(self) -> Self {
+        self.entities_eq(Vec::new())
+    }
+
+    /// Checks if the given entity is the only entity with a changed component.
+    ///
+    /// This can be inverted via [`Self::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut app = App::new();
+    /// let a = app.spawn(Health(5)).id();
+    /// app.added::<Health>();
+    ///
+    /// let b = app.spawn(Health(5)).id();
+    /// app.added::<Health>()
+    ///     .only(b)
+    ///     .not().only(a);
+    /// ```
+    pub fn only(self, entity: Entity) -> Self {
+        self.entities_eq(vec![entity])
+    }
+
+    fn reset_invert(mut self) -> Self {
+        self.invert = false;
+        self
+    }
+}
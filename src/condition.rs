@@ -0,0 +1,97 @@
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use crate::{mismatch, unexpected_match};
+
+/// A struct to perform tests on the result of a run condition, which is created via
+/// [`App::condition`].
+///
+/// ```
+/// use bevy_testing::p::*;
+///
+/// #[derive(Resource)]
+/// struct Lives(u32);
+///
+/// fn game_over(lives: Res<Lives>) -> bool {
+///     lives.0 == 0
+/// }
+///
+/// let mut app = App::new();
+/// app.insert_resource(Lives(1));
+/// app.condition(game_over).evaluates_to(false);
+///
+/// app.insert_resource(Lives(0));
+/// app.condition(game_over).evaluates_to(true);
+/// ```
+pub struct AssertCondition {
+    pub(crate) name: String,
+    pub(crate) value: bool,
+    pub(crate) invert: bool,
+}
+
+impl AssertCondition {
+    /// Returns an inverted [`AssertCondition`].
+    /// When chaining methods,
+    /// the inverted state gets reset after every method.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// let mut app = App::new();
+    /// app.condition(resource_exists::<Time>)
+    ///     .not().evaluates_to(true);
+    /// ```
+    #[allow(clippy::should_implement_trait)] // users should not need to import std::ops::Not
+    pub fn not(mut self) -> Self {
+        self.invert = !self.invert;
+        self
+    }
+
+    /// Checks if the condition returned `given`.
+    ///
+    /// This can be inverted via [`Self::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Resource)]
+    /// struct Score(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.insert_resource(Score(10));
+    ///
+    /// app.condition(resource_exists::<Score>)
+    ///     .evaluates_to(true);
+    /// app.condition(resource_exists::<Score>.and_then(|score: Res<Score>| score.0 > 100))
+    ///     .evaluates_to(false);
+    /// ```
+    pub fn evaluates_to(self, given: bool) -> Self {
+        if self.invert {
+            if self.value == given {
+                unexpected_match(
+                    &format!("The condition \"{}\" evaluates to {given}.", self.name),
+                    given,
+                );
+            }
+            return self.reset_invert();
+        }
+
+        if self.value != given {
+            mismatch(
+                &format!(
+                    "The condition \"{}\" evaluates to {}.",
+                    self.name, self.value
+                ),
+                given,
+                self.value,
+            );
+        }
+
+        self
+    }
+
+    fn reset_invert(mut self) -> Self {
+        self.invert = false;
+        self
+    }
+}
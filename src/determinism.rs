@@ -0,0 +1,166 @@
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use std::time::Duration;
+
+use bevy::time::TimeUpdateStrategy;
+use colored::Colorize;
+
+use crate::{
+    hash::{capture, world_hash},
+    print_failure, rng, TestRng,
+};
+
+/// The time every frame takes, unless the app chooses a [`TimeUpdateStrategy`] itself.
+const FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Checks that an app is deterministic, by running two instances of it in lockstep and comparing
+/// their [`world hashes`](App::world_hash) after every frame.
+///
+/// Both apps get a [`TestRng`] with the same seed, which is taken from `BEVY_TESTING_SEED` or
+/// chosen randomly and printed on failure like the seed of [`App::seed`], and unless the factory chose a [`TimeUpdateStrategy`],
+/// every frame takes 1/60 of a second.
+/// On the first frame where the hashes differ, the differences between the apps are printed.
+///
+/// ```
+/// use bevy_testing::p::*;
+/// use bevy_testing::{Determinism, TestRng};
+///
+/// #[derive(Component, Reflect)]
+/// #[reflect(Component)]
+/// struct Position(f32);
+///
+/// fn wander(mut query: Query<&mut Position>, mut rng: ResMut<TestRng>) {
+///     for mut position in &mut query {
+///         position.0 += rng.next_f32() - 0.5;
+///     }
+/// }
+///
+/// Determinism::check(
+///     || {
+///         let mut app = App::new();
+///         app.register_type::<Position>();
+///         app.add_systems(Update, wander);
+///         app.spawn(Position(0.0));
+///         app
+///     },
+///     100,
+/// );
+/// ```
+pub struct Determinism;
+
+impl Determinism {
+    /// Builds two apps via `build_app` and updates them `frames` times, panicking on the first
+    /// frame where their world hashes differ.
+    ///
+    /// ```should_panic
+    /// use std::collections::hash_map::RandomState;
+    /// use std::hash::{BuildHasher, Hasher};
+    ///
+    /// use bevy_testing::p::*;
+    /// use bevy_testing::Determinism;
+    ///
+    /// #[derive(Component, Reflect)]
+    /// #[reflect(Component)]
+    /// struct Position(u64);
+    ///
+    /// // the random state isn't seeded by the test
+    /// fn teleport(mut query: Query<&mut Position>) {
+    ///     for mut position in &mut query {
+    ///         position.0 = RandomState::new().build_hasher().finish() % 100;
+    ///     }
+    /// }
+    ///
+    /// Determinism::check(
+    ///     || {
+    ///         let mut app = App::new();
+    ///         app.register_type::<Position>();
+    ///         app.add_systems(Update, teleport);
+    ///         app.spawn(Position(0));
+    ///         app
+    ///     },
+    ///     10,
+    /// );
+    /// ```
+    pub fn check(build_app: impl Fn() -> App, frames: u32) {
+        Self::check_with_input(build_app, frames, |_, _| {});
+    }
+
+    /// Works like [`Determinism::check`], but calls `input` with each app and the number of the
+    /// frame before every update, e.g. to press keys or send events.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    /// use bevy_testing::Determinism;
+    ///
+    /// #[derive(Event)]
+    /// struct Jump;
+    ///
+    /// #[derive(Resource, Reflect, Default)]
+    /// #[reflect(Resource)]
+    /// struct Jumps(u32);
+    ///
+    /// fn count(mut events: EventReader<Jump>, mut jumps: ResMut<Jumps>) {
+    ///     jumps.0 += events.read().count() as u32;
+    /// }
+    ///
+    /// Determinism::check_with_input(
+    ///     || {
+    ///         let mut app = App::new();
+    ///         app.register_type::<Jumps>();
+    ///         app.init_resource::<Jumps>();
+    ///         app.add_event::<Jump>();
+    ///         app.add_systems(Update, count);
+    ///         app
+    ///     },
+    ///     30,
+    ///     |app, frame| {
+    ///         if frame % 10 == 0 {
+    ///             app.world_mut().send_event(Jump);
+    ///         }
+    ///     },
+    /// );
+    /// ```
+    pub fn check_with_input(
+        build_app: impl Fn() -> App,
+        frames: u32,
+        mut input: impl FnMut(&mut App, u32),
+    ) {
+        let seed = TestRng::from_env().seed();
+        rng::set_seed(seed);
+        let mut apps = [build_app(), build_app()];
+        for app in &mut apps {
+            app.insert_resource(TestRng::new(seed));
+            if !app.world().contains_resource::<TimeUpdateStrategy>() {
+                app.insert_resource(TimeUpdateStrategy::ManualDuration(FRAME_TIME));
+            }
+        }
+
+        for frame in 0..=frames {
+            if frame > 0 {
+                for app in &mut apps {
+                    input(app, frame);
+                    app.update_once();
+                }
+            }
+            let [a, b] = &apps;
+            if world_hash(a.world()) != world_hash(b.world()) {
+                diverged(a, b, frame);
+            }
+        }
+    }
+}
+
+fn diverged(a: &App, b: &App, frame: u32) -> ! {
+    if frame == 0 {
+        print_failure("The apps differ before the first update.");
+    } else {
+        print_failure(&format!("The apps diverged in frame {frame}."));
+    }
+    eprintln!(
+        "{}",
+        "Differences from the first to the second app:".bright_black()
+    );
+    eprint!("{}", capture(a.world()).diff(&capture(b.world())));
+    panic!("assertion failed");
+}
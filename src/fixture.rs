@@ -0,0 +1,232 @@
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use std::{collections::BTreeMap, ops::Index};
+
+#[cfg(feature = "scene")]
+use std::{fs, path::Path};
+
+#[cfg(feature = "scene")]
+use bevy::{
+    ecs::entity::EntityHashMap,
+    scene::{serde::SceneDeserializer, DynamicScene},
+};
+#[cfg(feature = "scene")]
+use serde::de::DeserializeSeed;
+
+/// An entity tree which can be spawned via [`App::spawn_tree`].
+/// Every entity gets a [`Name`], which is used to look up its id afterwards.
+///
+/// Usually this is created using the [`tree!`](crate::tree) macro.
+///
+/// ```
+/// use bevy_testing::p::*;
+/// use bevy_testing::SpawnTree;
+///
+/// #[derive(Component)]
+/// struct Button;
+///
+/// let mut app = App::new();
+/// let ids = app.spawn_tree(
+///     SpawnTree::new("menu", ())
+///         .child(SpawnTree::new("play", Button))
+///         .child(SpawnTree::new("quit", Button)),
+/// );
+///
+/// app.hierarchy(ids["menu"])
+///     .children_count(2);
+/// ```
+pub struct SpawnTree {
+    pub(crate) name: String,
+    pub(crate) insert: Box<dyn FnOnce(&mut EntityWorldMut)>,
+    pub(crate) children: Vec<SpawnTree>,
+}
+
+impl SpawnTree {
+    /// Creates a tree with a single entity, which has the given name and bundle.
+    pub fn new<B: Bundle>(name: impl Into<String>, bundle: B) -> Self {
+        Self {
+            name: name.into(),
+            insert: Box::new(move |entity| {
+                entity.insert(bundle);
+            }),
+            children: Vec::new(),
+        }
+    }
+
+    /// Adds a child tree below the root entity.
+    pub fn child(mut self, child: SpawnTree) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Adds multiple child trees below the root entity.
+    pub fn children(mut self, children: impl IntoIterator<Item = SpawnTree>) -> Self {
+        self.children.extend(children);
+        self
+    }
+
+    /// Spawns the tree, returning the root entity.
+    pub(crate) fn spawn(self, world: &mut World, ids: &mut NamedEntities) -> Entity {
+        let mut entity = world.spawn(Name::new(self.name.clone()));
+        (self.insert)(&mut entity);
+        let root = entity.id();
+        ids.insert(self.name, root);
+
+        for child in self.children {
+            let child = child.spawn(world, ids);
+            world.entity_mut(root).add_child(child);
+        }
+        root
+    }
+}
+
+/// Deserializes the scene file at `path` using the world's type registry and spawns it,
+/// returning the spawned entities which have a [`Name`].
+#[cfg(feature = "scene")]
+pub(crate) fn spawn_scene_file(world: &mut World, path: &Path) -> NamedEntities {
+    let registry = world
+        .get_resource_or_insert_with(AppTypeRegistry::default)
+        .clone();
+    {
+        // scene files usually contain names and hierarchies
+        let mut registry = registry.write();
+        registry.register::<Name>();
+        registry.register::<Parent>();
+        registry.register::<Children>();
+    }
+
+    let text = fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("failed to read scene file {}: {err}", path.display()));
+    let mut deserializer = ron::de::Deserializer::from_str(&text)
+        .unwrap_or_else(|err| panic!("failed to parse scene file {}: {err}", path.display()));
+    let scene: DynamicScene = SceneDeserializer {
+        type_registry: &registry.read(),
+    }
+    .deserialize(&mut deserializer)
+    .unwrap_or_else(|err| panic!("failed to deserialize scene file {}: {err}", path.display()));
+
+    let mut entity_map = EntityHashMap::default();
+    scene
+        .write_to_world(world, &mut entity_map)
+        .unwrap_or_else(|err| panic!("failed to spawn scene file {}: {err}", path.display()));
+
+    let mut ids = NamedEntities::default();
+    let mut entities = entity_map.into_values().collect::<Vec<_>>();
+    entities.sort();
+    for entity in entities {
+        if let Some(name) = world.get::<Name>(entity) {
+            ids.insert(name.to_string(), entity);
+        }
+    }
+    ids
+}
+
+/// Declares a [`SpawnTree`]. Every entity is written as `"name": bundle`, optionally
+/// followed by `=> [ ... ]` with its comma-separated children.
+///
+/// ```
+/// use bevy_testing::p::*;
+///
+/// #[derive(Component)]
+/// struct Hip;
+/// #[derive(Component)]
+/// struct Bone(f32);
+///
+/// let mut app = App::new();
+/// let ids = app.spawn_tree(tree! {
+///     "hip": (Hip, Bone(0.3)) => [
+///         "spine": Bone(0.5) => [
+///             "head": Bone(0.2),
+///         ],
+///         "leg": Bone(0.9),
+///     ]
+/// });
+///
+/// app.hierarchy(ids["hip"])
+///     .children_count(2)
+///     .depth(2);
+/// assert_eq!(app.component::<Bone>(ids["head"]).0, 0.2);
+/// ```
+#[macro_export]
+macro_rules! tree {
+    (@children [$($done:expr),*]) => {
+        ::std::vec![$($done),*]
+    };
+    (@children [$($done:expr),*]
+        $name:literal : $bundle:expr $(=> [$($children:tt)*])? $(, $($rest:tt)*)?
+    ) => {
+        $crate::tree!(@children
+            [$($done,)* $crate::tree!($name : $bundle $(=> [$($children)*])?)]
+            $($($rest)*)?
+        )
+    };
+    ($name:literal : $bundle:expr $(=> [$($children:tt)*])? $(,)?) => {
+        $crate::SpawnTree::new($name, $bundle)
+            $(.children($crate::tree!(@children [] $($children)*)))?
+    };
+}
+
+/// A map from names to the entities spawned by [`App::spawn_tree`] or `App::spawn_scene_file`.
+/// Indexing with a name which doesn't exist panics.
+///
+/// ```
+/// use bevy_testing::p::*;
+///
+/// let mut app = App::new();
+/// let ids = app.spawn_tree(tree! {
+///     "root": () => [ "child": () ]
+/// });
+///
+/// assert_eq!(ids.get("child"), Some(ids["child"]));
+/// assert_eq!(ids.get("other"), None);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NamedEntities {
+    entities: BTreeMap<String, Entity>,
+}
+
+impl NamedEntities {
+    /// Returns the entity with the given name.
+    pub fn get(&self, name: &str) -> Option<Entity> {
+        self.entities.get(name).copied()
+    }
+
+    /// Returns an iterator over all names and their entities, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Entity)> {
+        self.entities
+            .iter()
+            .map(|(name, entity)| (name.as_str(), *entity))
+    }
+
+    /// Returns the amount of named entities.
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Returns `true` if there are no named entities.
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Adds a name, panicking if it's already taken.
+    pub(crate) fn insert(&mut self, name: String, entity: Entity) {
+        if self.entities.contains_key(&name) {
+            panic!("the name \"{name}\" is used by multiple entities");
+        }
+        self.entities.insert(name, entity);
+    }
+}
+
+impl Index<&str> for NamedEntities {
+    type Output = Entity;
+
+    fn index(&self, name: &str) -> &Entity {
+        self.entities.get(name).unwrap_or_else(|| {
+            panic!(
+                "no entity is named \"{name}\", the names are {:?}",
+                self.entities.keys().collect::<Vec<_>>()
+            )
+        })
+    }
+}
@@ -0,0 +1,299 @@
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use std::{
+    collections::{HashMap, HashSet},
+    panic::{self, AssertUnwindSafe},
+};
+
+use bevy::{
+    app::MainScheduleOrder,
+    ecs::{
+        label::DynEq,
+        schedule::{ExecutorKind, InternedScheduleLabel, InternedSystemSet, ScheduleLabel},
+    },
+    utils::get_short_name,
+};
+use colored::Colorize;
+
+use crate::{
+    print_failure,
+    scenario::panic_message,
+    schedule::{after, children},
+    spy::{self, SystemSpy},
+    TestRng,
+};
+
+/// A test which runs many times, each time with a different order of the systems which access
+/// the same data without being ordered, created via
+/// [`Scenario::fuzz_system_order`](crate::Scenario::fuzz_system_order).
+///
+/// Bevy runs such systems in an arbitrary order, which may differ between machines, so a test
+/// passing locally doesn't prove that the outcome is independent of it.
+/// Here, every schedule runs on the single-threaded executor, and before a schedule is built,
+/// each of these pairs gets ordered according to a random order of the schedule's systems.
+/// Run `n` uses the seed `seed + n`, so the same seeds always lead to the same orders.
+///
+/// The test stops at the first failing seed, which is printed along with the chosen orders.
+///
+/// There are two limits:
+/// - Each run needs a fresh [`App`], so this is an entry point on [`Scenario`](crate::Scenario)
+///   which builds the app inside the test, instead of a method on an existing app.
+/// - Only systems whose data access conflicts are reordered. Systems which merely use
+///   [`Commands`] don't conflict, so the order in which their commands get applied is never fuzzed.
+///
+/// ```
+/// use bevy_testing::p::*;
+/// use bevy_testing::Scenario;
+///
+/// #[derive(Resource, Default)]
+/// struct Score(u32);
+///
+/// fn add_points(mut score: ResMut<Score>) {
+///     score.0 += 10;
+/// }
+///
+/// fn add_bonus(mut score: ResMut<Score>) {
+///     score.0 += 5;
+/// }
+///
+/// Scenario::fuzz_system_order(42, 16).run(|app| {
+///     app.init_resource::<Score>();
+///     app.add_systems(Update, (add_points, add_bonus));
+///     app.update_once();
+///
+///     assert_eq!(app.world().resource::<Score>().0, 15);
+/// });
+/// ```
+pub struct FuzzSystemOrder {
+    pub(crate) seed: u64,
+    pub(crate) runs: u32,
+}
+
+impl FuzzSystemOrder {
+    /// Runs the test once per seed, each with a fresh [`App`], and panics on the first failure.
+    ///
+    /// The orders are applied whenever the app updates, so the test should add its systems
+    /// before that and run its schedules via [`App::update`] or [`App::update_once`].
+    ///
+    /// ```should_panic
+    /// use bevy_testing::p::*;
+    /// use bevy_testing::Scenario;
+    ///
+    /// #[derive(Resource)]
+    /// struct Score(u32);
+    ///
+    /// fn add_points(mut score: ResMut<Score>) {
+    ///     score.0 += 10;
+    /// }
+    ///
+    /// // the bonus depends on the points being added first
+    /// fn double_score(mut score: ResMut<Score>) {
+    ///     score.0 *= 2;
+    /// }
+    ///
+    /// Scenario::fuzz_system_order(0, 16).run(|app| {
+    ///     app.insert_resource(Score(0));
+    ///     app.add_systems(Update, (add_points, double_score));
+    ///     app.update_once();
+    ///
+    ///     assert_eq!(app.world().resource::<Score>().0, 20);
+    /// });
+    /// ```
+    pub fn run(self, test: impl Fn(&mut App)) {
+        for run in 0..self.runs {
+            let seed = self.seed.wrapping_add(u64::from(run));
+            let mut app = App::new();
+            install(&mut app, seed);
+            let result = panic::catch_unwind(AssertUnwindSafe(|| test(&mut app)));
+            let Err(payload) = result else {
+                continue;
+            };
+
+            let message = panic_message(payload);
+            print_failure(&format!(
+                "The test failed with seed {seed} (run {} of {}).",
+                run + 1,
+                self.runs
+            ));
+            eprintln!("{} {message}", "Panic:".bright_black());
+            let orderings = app
+                .world()
+                .get_resource::<OrderFuzzer>()
+                .map(|fuzzer| fuzzer.orderings.clone())
+                .unwrap_or_default();
+            if !orderings.is_empty() {
+                eprintln!("{}", "Orders:".bright_black());
+                for (label, a, b) in orderings {
+                    eprintln!("  {a} before {b} {}", format!("({label:?})").bright_black());
+                }
+            }
+            eprintln!(
+                "{}",
+                format!("Rerun only this seed via `Scenario::fuzz_system_order({seed}, 1)`.")
+                    .bright_black()
+            );
+            panic!("assertion failed");
+        }
+    }
+}
+
+/// The state of the orders chosen for one run.
+#[derive(Resource)]
+struct OrderFuzzer {
+    rng: TestRng,
+    sets: u32,
+    orderings: Vec<(InternedScheduleLabel, String, String)>,
+}
+
+/// Runs at the start of every frame, before any schedule which changed gets built.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+struct FuzzSchedule;
+
+/// An empty set which orders one system before another.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+struct FuzzOrder(u32);
+
+/// Wraps a set of a schedule graph, so that it can be interned as the set it wraps.
+#[derive(Debug)]
+struct GraphSet(Box<dyn SystemSet>);
+
+impl SystemSet for GraphSet {
+    fn system_type(&self) -> Option<std::any::TypeId> {
+        self.0.system_type()
+    }
+
+    fn is_anonymous(&self) -> bool {
+        self.0.is_anonymous()
+    }
+
+    fn dyn_clone(&self) -> Box<dyn SystemSet> {
+        self.0.dyn_clone()
+    }
+
+    fn as_dyn_eq(&self) -> &dyn DynEq {
+        self.0.as_dyn_eq()
+    }
+
+    fn dyn_hash(&self, state: &mut dyn std::hash::Hasher) {
+        self.0.dyn_hash(state);
+    }
+}
+
+fn install(app: &mut App, seed: u64) {
+    app.insert_resource(OrderFuzzer {
+        rng: TestRng::new(seed),
+        sets: 0,
+        orderings: Vec::new(),
+    });
+    let mut order = app.world_mut().resource_mut::<MainScheduleOrder>();
+    order.insert_startup_before(PreStartup, FuzzSchedule);
+    order.insert_before(First, FuzzSchedule);
+    app.add_systems(FuzzSchedule, order_schedules);
+}
+
+fn order_schedules(world: &mut World) {
+    // building a schedule moves the conditions out of its graph
+    if world.contains_resource::<SystemSpy>() {
+        spy::record_conditions(world);
+    }
+    // sorted, so that the same seed always leads to the same orders
+    let mut labels = world
+        .resource::<Schedules>()
+        .iter()
+        .filter(|(_, schedule)| schedule.graph().systems().next().is_some())
+        .map(|(_, schedule)| schedule.label())
+        .collect::<Vec<_>>();
+    labels.sort_by_cached_key(|label| format!("{label:?}"));
+    for label in labels {
+        world.schedule_scope(label, order_schedule);
+    }
+}
+
+/// Orders every pair of conflicting systems according to a random topological order.
+fn order_schedule(world: &mut World, schedule: &mut Schedule) {
+    schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+    // an error surfaces once the schedule runs
+    if schedule.initialize(world).is_err() {
+        return;
+    }
+
+    let graph = schedule.graph();
+    let apply_deferred = IntoSystem::system_type_id(&apply_deferred);
+    let systems = schedule
+        .systems()
+        .expect("the schedule was initialized")
+        .filter(|(_, system)| system.type_id() != apply_deferred)
+        .map(|(id, system)| (id, get_short_name(&system.name())))
+        .collect::<HashMap<_, _>>();
+    let mut fuzzer = world.resource_mut::<OrderFuzzer>();
+
+    let mut remaining = schedule
+        .systems()
+        .expect("the schedule was initialized")
+        .map(|(id, _)| id)
+        .filter(|id| systems.contains_key(id))
+        .collect::<Vec<_>>();
+    let successors = remaining
+        .iter()
+        .map(|id| (*id, after(graph, *id)))
+        .collect::<HashMap<_, HashSet<_>>>();
+    let mut position = HashMap::new();
+    while !remaining.is_empty() {
+        let ready = (0..remaining.len())
+            .filter(|i| {
+                !remaining
+                    .iter()
+                    .any(|other| successors[other].contains(&remaining[*i]))
+            })
+            .collect::<Vec<_>>();
+        let index = ready[fuzzer.rng.below(ready.len() as u64) as usize];
+        position.insert(remaining.remove(index), position.len());
+    }
+
+    // systems added more than once have no set which could be ordered
+    let children = children(graph);
+    let type_set = |system| {
+        graph
+            .system_sets()
+            .find(|(id, set, _)| {
+                set.system_type().is_some()
+                    && children.get(id).is_some_and(|nodes| *nodes == [system])
+            })
+            .map(|(_, set, _)| GraphSet(set.dyn_clone()).intern())
+    };
+    let mut orders = Vec::<(InternedSystemSet, InternedSystemSet)>::new();
+    for (a, b, _) in graph.conflicting_systems() {
+        let (Some(a_position), Some(b_position)) = (position.get(a), position.get(b)) else {
+            continue;
+        };
+        let (first, second) = if a_position < b_position {
+            (*a, *b)
+        } else {
+            (*b, *a)
+        };
+        let (Some(first_set), Some(second_set)) = (type_set(first), type_set(second)) else {
+            continue;
+        };
+        orders.push((first_set, second_set));
+        fuzzer.orderings.push((
+            schedule.label(),
+            systems[&first].clone(),
+            systems[&second].clone(),
+        ));
+    }
+
+    if orders.is_empty() {
+        return;
+    }
+    for (first, second) in orders {
+        let set = FuzzOrder(fuzzer.sets);
+        fuzzer.sets += 1;
+        schedule.configure_sets(
+            set.after_ignore_deferred(first)
+                .before_ignore_deferred(second),
+        );
+    }
+    // as above, an error surfaces once the schedule runs
+    let _ = schedule.initialize(world);
+}
@@ -0,0 +1,232 @@
+use std::{
+    collections::HashMap,
+    env,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    prelude::*,
+    reflect::{ReflectRef, ReflectSerialize, TypeRegistry, VariantType},
+};
+use colored::Colorize;
+
+use crate::{print_failure, WorldSnapshot};
+
+/// The environment variable which accepts changed snapshots when set to `1`.
+const UPDATE_VAR: &str = "BEVY_TESTING_UPDATE";
+
+/// Renders a snapshot as RON.
+///
+/// Entities are sorted by their [`Name`], unnamed entities first, and then by their components.
+/// Entity ids are reused after despawning, so they only decide between equal entities.
+/// They are replaced by the position in that order, so unrelated spawns don't change the output.
+/// Entities without any captured component are left out.
+pub(crate) fn to_ron(snapshot: &WorldSnapshot, registry: &TypeRegistry) -> String {
+    let mut entities = snapshot
+        .entities
+        .iter()
+        .filter(|(_, components)| !components.is_empty())
+        .map(|(entity, _)| *entity)
+        .collect::<Vec<_>>();
+    // references to other entities can't be rendered before the order is known
+    let unordered = HashMap::new();
+    entities.sort_by_cached_key(|entity| {
+        let components = snapshot.entities[entity]
+            .iter()
+            .map(|(type_path, value)| {
+                let value = render_value(value.as_ref(), registry, &unordered);
+                format!("{type_path}: {value}")
+            })
+            .collect::<Vec<_>>();
+        (snapshot.names.get(entity), components, entity.index())
+    });
+    let ids = entities
+        .iter()
+        .enumerate()
+        .map(|(id, entity)| (*entity, id))
+        .collect::<HashMap<_, _>>();
+
+    let mut out = String::from("(\n    entities: [\n");
+    for entity in &entities {
+        out += "        (\n";
+        writeln!(out, "            id: {},", ids[entity]).unwrap();
+        if let Some(name) = snapshot.names.get(entity) {
+            writeln!(out, "            name: {name:?},").unwrap();
+        }
+        out += "            components: {\n";
+        for (type_path, value) in &snapshot.entities[entity] {
+            let value = render_value(value.as_ref(), registry, &ids);
+            writeln!(out, "                {type_path:?}: {value},").unwrap();
+        }
+        out += "            },\n        ),\n";
+    }
+    out += "    ],\n    resources: {\n";
+    for (type_path, value) in &snapshot.resources {
+        let value = render_value(value.as_ref(), registry, &ids);
+        writeln!(out, "        {type_path:?}: {value},").unwrap();
+    }
+    out += "    },\n)\n";
+    out
+}
+
+/// Renders a value as RON, with the entries of maps sorted and entities replaced by their
+/// position in `ids`, so equal values always lead to the same output.
+///
+/// Sets are opaque to reflection, so only their type is rendered.
+pub(crate) fn render_value(
+    value: &dyn Reflect,
+    registry: &TypeRegistry,
+    ids: &HashMap<Entity, usize>,
+) -> String {
+    let render = |value: &dyn Reflect| render_value(value, registry, ids);
+    let join = |items: Vec<String>| items.join(", ");
+
+    match value.reflect_ref() {
+        ReflectRef::Struct(value) => format!(
+            "({})",
+            join(
+                (0..value.field_len())
+                    .map(|i| format!(
+                        "{}: {}",
+                        value.name_at(i).unwrap(),
+                        render(value.field_at(i).unwrap())
+                    ))
+                    .collect()
+            )
+        ),
+        ReflectRef::TupleStruct(value) => {
+            format!("({})", join(value.iter_fields().map(render).collect()))
+        }
+        ReflectRef::Tuple(value) => {
+            format!("({})", join(value.iter_fields().map(render).collect()))
+        }
+        ReflectRef::List(value) => format!("[{}]", join(value.iter().map(render).collect())),
+        ReflectRef::Array(value) => format!("[{}]", join(value.iter().map(render).collect())),
+        ReflectRef::Map(value) => {
+            let mut entries = value
+                .iter()
+                .map(|(key, value)| format!("{}: {}", render(key), render(value)))
+                .collect::<Vec<_>>();
+            // maps don't have a stable iteration order
+            entries.sort();
+            format!("{{{}}}", join(entries))
+        }
+        ReflectRef::Enum(value) => {
+            let fields = (0..value.field_len()).map(|i| {
+                let field = render(value.field_at(i).unwrap());
+                match value.name_at(i) {
+                    Some(name) => format!("{name}: {field}"),
+                    None => field,
+                }
+            });
+            match value.variant_type() {
+                VariantType::Unit => value.variant_name().to_owned(),
+                _ => format!("{}({})", value.variant_name(), join(fields.collect())),
+            }
+        }
+        ReflectRef::Value(value) => {
+            if let Some(entity) = value.as_any().downcast_ref::<Entity>() {
+                return match ids.get(entity) {
+                    Some(id) => format!("Entity({id})"),
+                    None => "Entity(None)".to_owned(),
+                };
+            }
+            registry
+                .get_type_data::<ReflectSerialize>(value.as_any().type_id())
+                .and_then(|serialize| {
+                    ron::to_string(serialize.get_serializable(value).borrow()).ok()
+                })
+                .unwrap_or_else(|| format!("{value:?}"))
+        }
+    }
+}
+
+fn snapshot_dir() -> PathBuf {
+    let root = env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    root.join("tests").join("snapshots")
+}
+
+/// Compares `actual` to the snapshot file called `name`, creating or updating it if allowed.
+pub(crate) fn assert_golden(name: &str, actual: &str) {
+    let path = snapshot_dir().join(format!("{name}.ron"));
+    let update = env::var(UPDATE_VAR).is_ok_and(|value| value == "1");
+
+    match fs::read_to_string(&path) {
+        Ok(expected) if expected.replace("\r\n", "\n") == actual => {}
+        Ok(expected) if !update => {
+            print_failure(&format!("The snapshot \"{name}\" changed."));
+            eprintln!("{} {}", "File:".bright_black(), path.display());
+            eprintln!();
+            print_diff(&expected, actual);
+            eprintln!();
+            eprintln!(
+                "{}",
+                format!("Rerun with {UPDATE_VAR}=1 to accept the new snapshot.").bright_black()
+            );
+            panic!("assertion failed");
+        }
+        Err(_) if !update && env::var_os("CI").is_some() => {
+            print_failure(&format!("The snapshot \"{name}\" doesn't exist."));
+            eprintln!("{} {}", "File:".bright_black(), path.display());
+            eprintln!();
+            eprintln!("{actual}");
+            panic!("assertion failed");
+        }
+        _ => write_snapshot(&path, actual),
+    }
+}
+
+fn write_snapshot(path: &Path, content: &str) {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).unwrap_or_else(|err| {
+            panic!(
+                "failed to create snapshot directory {}: {err}",
+                dir.display()
+            )
+        });
+    }
+    fs::write(path, content)
+        .unwrap_or_else(|err| panic!("failed to write snapshot {}: {err}", path.display()));
+    eprintln!(
+        "{} {}",
+        "Stored snapshot".bright_black(),
+        path.display().to_string().bright_black()
+    );
+}
+
+/// Prints a line diff between the stored and the new snapshot.
+fn print_diff(expected: &str, actual: &str) {
+    let old = expected.lines().collect::<Vec<_>>();
+    let new = actual.lines().collect::<Vec<_>>();
+
+    // longest common subsequence table
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            eprintln!("{}", format!("  {}", old[i]).bright_black());
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            eprintln!("{}", format!("- {}", old[i]).red());
+            i += 1;
+        } else {
+            eprintln!("{}", format!("+ {}", new[j]).green());
+            j += 1;
+        }
+    }
+}
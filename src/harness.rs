@@ -0,0 +1,363 @@
+//! A test harness which runs [`bevy_test`](crate::bevy_test)s and writes machine-readable reports.
+//!
+//! This module requires the `harness` feature.
+//!
+//! Every `#[bevy_test]` in a test target gets registered with the harness. To use it, disable the
+//! default harness for the target in `Cargo.toml`:
+//!
+//! ```toml
+//! [[test]]
+//! name = "game"
+//! harness = false
+//! ```
+//!
+//! and call [`main`] from the target's `main` function:
+//!
+//! ```
+//! use bevy_testing::p::*;
+//! use bevy_testing::bevy_test;
+//!
+//! #[derive(Resource)]
+//! struct Score(u32);
+//!
+//! #[bevy_test(frames = 10)]
+//! fn starts_without_score(app: &mut App) {
+//!     app.insert_resource(Score(0));
+//!     assert_eq!(app.world().resource::<Score>().0, 0);
+//! }
+//!
+//! fn main() {
+//!     bevy_testing::harness::main();
+//! }
+//! ```
+//!
+//! The usual arguments of `cargo test` work, e.g. filters, `--exact` and `--test-threads`.
+//! `--nocapture` is accepted, but output is never captured, so it is always printed.
+//! The results can be written to files via environment variables:
+//!
+//! variable              | description
+//! ----------------------|--
+//! `BEVY_TESTING_JUNIT`  | path of a JUnit XML report
+//! `BEVY_TESTING_JSON`   | path of a JSON report
+//!
+//! Both contain the duration, the amount of frames and, for failed tests, the panic message and
+//! the details printed by failing assertions without colors.
+//! Frames are counted via [`App::update_once`] and [`App::update_n_times`], or via the
+//! [`FrameCount`](bevy::core::FrameCount) of the app if it has one, which also sees [`App::update`].
+//!
+//! Other tests, such as the scenario files of a [`ScenarioRunner`](crate::ScenarioRunner), can be
+//! run alongside them via [`run_with`].
+//!
+//! Targets using the default harness, such as unit tests, keep running their tests with it.
+
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use std::{
+    cell::RefCell,
+    env,
+    fmt::Write as _,
+    fs, mem,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use libtest_mimic::Failed;
+pub use libtest_mimic::{Arguments, Conclusion, Trial};
+
+use crate::{scenario::panic_message, trace::json_string};
+
+/// A test registered by [`bevy_test`](crate::bevy_test).
+#[doc(hidden)]
+pub struct BevyTest {
+    pub name: &'static str,
+    pub should_panic: bool,
+    pub expected: Option<&'static str>,
+    pub run: fn(),
+}
+
+inventory::collect!(BevyTest);
+
+/// The outcome of a test, as written to the reports.
+struct TestResult {
+    name: String,
+    failure: Option<String>,
+    details: Vec<String>,
+    frames: u32,
+    duration: Duration,
+}
+
+/// The state of the test running on the current thread.
+struct Current {
+    frames: u32,
+    details: Vec<String>,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Current>> = const { RefCell::new(None) };
+}
+
+/// The results of the tests which ran, for the reports.
+static RESULTS: Mutex<Vec<TestResult>> = Mutex::new(Vec::new());
+
+/// Stores a line of an assertion's output for the report, if a test is running in the harness.
+pub(crate) fn record_detail(line: String) {
+    CURRENT.with_borrow_mut(|current| {
+        if let Some(current) = current {
+            current.details.push(line);
+        }
+    });
+}
+
+/// Counts an update done via [`App::update_once`] or [`App::update_n_times`],
+/// if a test is running in the harness.
+pub(crate) fn count_frame() {
+    CURRENT.with_borrow_mut(|current| {
+        if let Some(current) = current {
+            current.frames += 1;
+        }
+    });
+}
+
+/// Raises the frame count of the running test to the [`FrameCount`](bevy::core::FrameCount)
+/// of its app, which also sees updates done via [`App::update`].
+pub(crate) fn observe_frame_count(frames: u32) {
+    CURRENT.with_borrow_mut(|current| {
+        if let Some(current) = current {
+            current.frames = current.frames.max(frames);
+        }
+    });
+}
+
+/// Parses the command line arguments, runs all registered tests and exits the process.
+pub fn main() -> ! {
+    run(&Arguments::from_args()).exit()
+}
+
+/// Runs all registered tests with the given arguments and writes the reports requested via
+/// environment variables.
+pub fn run(args: &Arguments) -> Conclusion {
+    run_with(args, Vec::new())
+}
+
+/// Works like [`run`], but also runs the given trials, e.g. those of
+/// [`ScenarioRunner::trials`](crate::ScenarioRunner::trials).
+///
+/// ```no_run
+/// use bevy_testing::harness::{self, Arguments, Trial};
+///
+/// fn main() {
+///     let smoke = Trial::test("smoke", || Ok(()));
+///     harness::run_with(&Arguments::from_args(), vec![smoke]).exit();
+/// }
+/// ```
+pub fn run_with(args: &Arguments, trials: Vec<Trial>) -> Conclusion {
+    let mut tests = inventory::iter::<BevyTest>
+        .into_iter()
+        .map(|test| {
+            // module paths start with the name of the crate, which libtest omits
+            let name = test
+                .name
+                .split_once("::")
+                .map_or(test.name, |(_, name)| name);
+            trial(name.to_owned(), test.should_panic, test.expected, test.run)
+        })
+        .chain(trials)
+        .collect::<Vec<_>>();
+    tests.sort_by(|a, b| a.name().cmp(b.name()));
+
+    let conclusion = libtest_mimic::run(args, tests);
+    if !args.list {
+        let mut results = mem::take(&mut *RESULTS.lock().unwrap());
+        results.sort_by(|a, b| a.name.cmp(&b.name));
+        if let Some(path) = env::var_os("BEVY_TESTING_JUNIT") {
+            write_report(Path::new(&path), &junit(&results));
+        }
+        if let Some(path) = env::var_os("BEVY_TESTING_JSON") {
+            write_report(Path::new(&path), &json(&results));
+        }
+    }
+    conclusion
+}
+
+/// Creates a trial which runs `run` like a [`bevy_test`](crate::bevy_test), so that its result
+/// is part of the reports.
+pub(crate) fn trial(
+    name: String,
+    should_panic: bool,
+    expected: Option<&'static str>,
+    run: impl Fn() + Send + 'static,
+) -> Trial {
+    Trial::test(name.clone(), move || {
+        let result = run_one(name, should_panic, expected, &run);
+        let outcome = match &result.failure {
+            Some(failure) => Err(Failed::from(failure)),
+            None => Ok(()),
+        };
+        RESULTS.lock().unwrap().push(result);
+        outcome
+    })
+}
+
+fn run_one(name: String, should_panic: bool, expected: Option<&str>, run: &dyn Fn()) -> TestResult {
+    CURRENT.set(Some(Current {
+        frames: 0,
+        details: Vec::new(),
+    }));
+    let start = Instant::now();
+    let result = panic::catch_unwind(AssertUnwindSafe(run));
+    let duration = start.elapsed();
+    let (frames, details) = CURRENT
+        .take()
+        .map(|current| (current.frames, current.details))
+        .unwrap_or_default();
+
+    let failure = match (result, should_panic) {
+        (Ok(()), false) => None,
+        (Ok(()), true) => Some("the test didn't panic as expected".to_owned()),
+        (Err(payload), false) => Some(panic_message(payload)),
+        (Err(payload), true) => {
+            let message = panic_message(payload);
+            match expected {
+                Some(expected) if !message.contains(expected) => Some(format!(
+                    "the panic message \"{message}\" doesn't contain \"{expected}\""
+                )),
+                _ => None,
+            }
+        }
+    };
+    TestResult {
+        name,
+        // the details only explain unexpected panics
+        details: if failure.is_some() {
+            details
+        } else {
+            Vec::new()
+        },
+        failure,
+        frames,
+        duration,
+    }
+}
+
+fn write_report(path: &Path, content: &str) {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)
+            .unwrap_or_else(|err| panic!("failed to create directory {}: {err}", dir.display()));
+    }
+    fs::write(path, content)
+        .unwrap_or_else(|err| panic!("failed to write report {}: {err}", path.display()));
+}
+
+fn junit(results: &[TestResult]) -> String {
+    let failures = results
+        .iter()
+        .filter(|result| result.failure.is_some())
+        .count();
+    let time = results
+        .iter()
+        .map(|result| result.duration)
+        .sum::<Duration>();
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    writeln!(
+        out,
+        "<testsuites tests=\"{}\" failures=\"{failures}\" time=\"{:.3}\">",
+        results.len(),
+        time.as_secs_f64()
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "  <testsuite name=\"bevy_testing\" tests=\"{}\" failures=\"{failures}\" time=\"{:.3}\">",
+        results.len(),
+        time.as_secs_f64()
+    )
+    .unwrap();
+    for result in results {
+        let (class, name) = result
+            .name
+            .rsplit_once("::")
+            .unwrap_or(("", result.name.as_str()));
+        writeln!(
+            out,
+            "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\">",
+            xml_escape(class),
+            xml_escape(name),
+            result.duration.as_secs_f64()
+        )
+        .unwrap();
+        writeln!(out, "      <properties>").unwrap();
+        writeln!(
+            out,
+            "        <property name=\"frames\" value=\"{}\"/>",
+            result.frames
+        )
+        .unwrap();
+        writeln!(out, "      </properties>").unwrap();
+        if let Some(failure) = &result.failure {
+            writeln!(
+                out,
+                "      <failure message=\"{}\">{}</failure>",
+                xml_escape(failure),
+                xml_escape(&result.details.join("\n"))
+            )
+            .unwrap();
+        }
+        writeln!(out, "    </testcase>").unwrap();
+    }
+    out += "  </testsuite>\n</testsuites>\n";
+    out
+}
+
+fn json(results: &[TestResult]) -> String {
+    let mut out = String::from("[\n");
+    for (i, result) in results.iter().enumerate() {
+        write!(
+            out,
+            "  {{\"name\":{},\"passed\":{},\"frames\":{},\"duration\":{:.3}",
+            json_string(&result.name),
+            result.failure.is_none(),
+            result.frames,
+            result.duration.as_secs_f64()
+        )
+        .unwrap();
+        if let Some(failure) = &result.failure {
+            let details = result
+                .details
+                .iter()
+                .map(|line| json_string(line))
+                .collect::<Vec<_>>();
+            write!(
+                out,
+                ",\"message\":{},\"details\":[{}]",
+                json_string(failure),
+                details.join(",")
+            )
+            .unwrap();
+        }
+        out += if i + 1 == results.len() {
+            "}\n"
+        } else {
+            "},\n"
+        };
+    }
+    out += "]\n";
+    out
+}
+
+fn xml_escape(value: &str) -> String {
+    let mut out = String::new();
+    for c in value.chars() {
+        match c {
+            '&' => out += "&amp;",
+            '<' => out += "&lt;",
+            '>' => out += "&gt;",
+            '"' => out += "&quot;",
+            '\'' => out += "&apos;",
+            c => out.push(c),
+        }
+    }
+    out
+}
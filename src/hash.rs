@@ -0,0 +1,118 @@
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hasher,
+};
+
+use colored::Colorize;
+
+use crate::{golden, print_failure, SnapshotFilter, WorldSnapshot};
+
+/// Hashes every reflectable component and resource of the world, in the order of the entities.
+pub(crate) fn world_hash(world: &World) -> u64 {
+    let mut hasher = Fnv::default();
+    for (entity, type_path, value) in values(world) {
+        if let Some(entity) = entity {
+            hasher.write(&entity.to_le_bytes());
+        }
+        hasher.write(type_path.as_bytes());
+        hasher.write(value.as_bytes());
+    }
+    hasher.finish()
+}
+
+/// Panics with the hashes of the individual types if the hash of the world isn't `expected`.
+pub(crate) fn assert_world_hash_eq(world: &World, expected: u64) {
+    let hash = world_hash(world);
+    if hash == expected {
+        return;
+    }
+
+    let mut types = BTreeMap::<String, (usize, Fnv)>::new();
+    for (entity, type_path, value) in values(world) {
+        let (count, hasher) = types.entry(type_path).or_default();
+        *count += 1;
+        if let Some(entity) = entity {
+            hasher.write(&entity.to_le_bytes());
+        }
+        hasher.write(value.as_bytes());
+    }
+    print_failure(&format!(
+        "The world hash is {hash:016x} instead of {expected:016x}."
+    ));
+    eprintln!("{}", "Hashes per type:".bright_black());
+    for (type_path, (count, hasher)) in types {
+        eprintln!(
+            "  {:016x} {type_path} {}",
+            hasher.finish(),
+            format!("({count}x)").bright_black()
+        );
+    }
+    eprintln!(
+        "{}",
+        "Compare them to the hashes printed by a run with the expected hash.".bright_black()
+    );
+    panic!("assertion failed");
+}
+
+/// Captures every reflectable component and resource which is part of the hash.
+pub(crate) fn capture(world: &World) -> WorldSnapshot {
+    // leaves out the time, which advances with the real time
+    WorldSnapshot::capture(world, &SnapshotFilter::all())
+}
+
+/// Returns the rendered components in the order of the entities, followed by the resources.
+///
+/// Entities are replaced by their position in that order, and values are rendered like in
+/// snapshot files, so maps don't depend on their iteration order.
+fn values(world: &World) -> Vec<(Option<u64>, String, String)> {
+    let snapshot = capture(world);
+    let registry = world.resource::<AppTypeRegistry>().read();
+    let ids = snapshot
+        .entities
+        .keys()
+        .enumerate()
+        .map(|(id, entity)| (*entity, id))
+        .collect::<HashMap<_, _>>();
+    let render = |value: &dyn Reflect| golden::render_value(value, &registry, &ids);
+
+    let components = snapshot.entities.iter().flat_map(|(entity, components)| {
+        components.iter().map(|(type_path, value)| {
+            (
+                Some(ids[entity] as u64),
+                type_path.clone(),
+                render(value.as_ref()),
+            )
+        })
+    });
+    let resources = snapshot
+        .resources
+        .iter()
+        .map(|(type_path, value)| (None, type_path.clone(), render(value.as_ref())));
+    components.chain(resources).collect()
+}
+
+/// The 64 bit FNV-1a hash, which unlike the hasher of the standard library doesn't depend on
+/// the Rust version, so hashes can be stored.
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(0xCBF2_9CE4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01B3);
+        }
+    }
+}
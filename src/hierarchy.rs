@@ -0,0 +1,336 @@
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use std::{any::type_name, fmt::Debug};
+
+use bevy::{ecs::query::ReadOnlyQueryData, utils::get_short_name};
+use colored::Colorize;
+
+use crate::{print_failure, query::AssertQuery};
+
+/// A struct to perform tests on the entity tree below an entity, which is created via [`App::hierarchy`].
+///
+/// On failure, the whole tree is printed along with the names and components of its entities.
+///
+/// ```
+/// use bevy_testing::p::*;
+///
+/// #[derive(Component)]
+/// struct Button;
+///
+/// let mut app = App::new();
+/// let menu = app.spawn(Name::new("menu")).id();
+/// app.entity_mut(menu).with_children(|menu| {
+///     menu.spawn(Button);
+///     menu.spawn(Button).with_children(|button| {
+///         button.spawn(Name::new("label"));
+///     });
+/// });
+///
+/// app.hierarchy(menu)
+///     .children_count(2)
+///     .has_child_with::<Button>()
+///     .depth(2);
+/// ```
+pub struct AssertHierarchy<'w> {
+    pub(crate) world: &'w mut World,
+    pub(crate) root: Entity,
+    pub(crate) invert: bool,
+}
+
+impl<'w> AssertHierarchy<'w> {
+    /// Returns an inverted [`AssertHierarchy`].
+    /// When chaining methods,
+    /// the inverted state gets reset after every method.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Button;
+    ///
+    /// let mut app = App::new();
+    /// let menu = app.spawn_empty().id();
+    ///
+    /// app.hierarchy(menu)
+    ///     .not().has_child_with::<Button>()
+    ///     .not().children_count(1);
+    /// ```
+    #[allow(clippy::should_implement_trait)] // users should not need to import std::ops::Not
+    pub fn not(mut self) -> Self {
+        self.invert = !self.invert;
+        self
+    }
+
+    /// Checks if any direct child of the root has the component `T`.
+    /// Despawned entities which are still listed in [`Children`] are skipped.
+    ///
+    /// This can be inverted via [`Self::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Button;
+    /// #[derive(Component)]
+    /// struct Label;
+    ///
+    /// let mut app = App::new();
+    /// let menu = app.spawn_empty().id();
+    /// app.entity_mut(menu).with_children(|menu| {
+    ///     menu.spawn(Button).with_children(|button| {
+    ///         button.spawn(Label);
+    ///     });
+    /// });
+    ///
+    /// app.hierarchy(menu)
+    ///     .has_child_with::<Button>()
+    ///     .not().has_child_with::<Label>();
+    /// ```
+    pub fn has_child_with<T: Component>(self) -> Self {
+        let found = self
+            .live_children(self.root)
+            .any(|child| self.world.entity(child).contains::<T>());
+        if self.invert {
+            if found {
+                self.fail(&format!(
+                    "A child of the entity has the component \"{}\".",
+                    type_name::<T>()
+                ));
+            }
+            return self.reset_invert();
+        }
+
+        if !found {
+            self.fail(&format!(
+                "No child of the entity has the component \"{}\".",
+                type_name::<T>()
+            ));
+        }
+
+        self
+    }
+
+    /// Checks if the root has exactly `given` direct children.
+    /// Despawned entities which are still listed in [`Children`] are not counted.
+    ///
+    /// This can be inverted via [`Self::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// let mut app = App::new();
+    /// let menu = app.spawn_empty().id();
+    /// app.entity_mut(menu).with_children(|menu| {
+    ///     menu.spawn_empty();
+    ///     menu.spawn_empty().with_children(|child| {
+    ///         child.spawn_empty();
+    ///     });
+    /// });
+    ///
+    /// app.hierarchy(menu)
+    ///     .children_count(2)
+    ///     .not().children_count(3);
+    /// ```
+    pub fn children_count(self, given: usize) -> Self {
+        let found = self.live_children(self.root).count();
+        if self.invert {
+            if found == given {
+                self.fail(&format!("The entity has {given} children."));
+            }
+            return self.reset_invert();
+        }
+
+        if found != given {
+            self.fail(&format!(
+                "The entity has {found} children instead of {given}."
+            ));
+        }
+
+        self
+    }
+
+    /// Checks if the longest path from the root to one of its descendants has `given` steps.
+    /// An entity without children has a depth of `0`, and despawned children are skipped.
+    ///
+    /// This can be inverted via [`Self::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// let mut app = App::new();
+    /// let menu = app.spawn_empty().id();
+    /// app.entity_mut(menu).with_children(|menu| {
+    ///     menu.spawn_empty();
+    ///     menu.spawn_empty().with_children(|child| {
+    ///         child.spawn_empty();
+    ///     });
+    /// });
+    ///
+    /// app.hierarchy(menu)
+    ///     .depth(2)
+    ///     .not().depth(1);
+    /// ```
+    pub fn depth(self, given: usize) -> Self {
+        let found = self.depth_of(self.root);
+        if self.invert {
+            if found == given {
+                self.fail(&format!("The tree has a depth of {given}."));
+            }
+            return self.reset_invert();
+        }
+
+        if found != given {
+            self.fail(&format!(
+                "The tree has a depth of {found} instead of {given}."
+            ));
+        }
+
+        self
+    }
+
+    /// Prints the tree to stderr, which can be useful while writing a test.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// let mut app = App::new();
+    /// let menu = app.spawn(Name::new("menu")).id();
+    /// app.entity_mut(menu).with_children(|menu| {
+    ///     menu.spawn(Name::new("play"));
+    /// });
+    ///
+    /// app.hierarchy(menu).print();
+    /// ```
+    pub fn print(self) -> Self {
+        eprintln!("{}", self.tree());
+        self
+    }
+
+    /// Returns an [`AssertQuery`] over all descendants of the root which match the query `D`.
+    /// The root itself is not included.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component, Debug, PartialEq)]
+    /// struct Label(&'static str);
+    ///
+    /// let mut app = App::new();
+    /// app.spawn(Label("outside"));
+    /// let menu = app.spawn(Label("menu")).id();
+    /// app.entity_mut(menu).with_children(|menu| {
+    ///     menu.spawn_empty().with_children(|button| {
+    ///         button.spawn(Label("play"));
+    ///     });
+    /// });
+    ///
+    /// app.hierarchy(menu)
+    ///     .descendants::<&Label>()
+    ///     .matches(vec![&Label("play")]);
+    /// ```
+    pub fn descendants<D: ReadOnlyQueryData>(self) -> AssertQuery<'w, D>
+    where
+        D::Item<'w>: PartialEq + Debug,
+    {
+        let mut descendants = Vec::new();
+        self.collect_descendants(self.root, &mut descendants);
+
+        let mut query = self.world.query::<D>();
+        let world: &'w World = self.world;
+        AssertQuery {
+            query: query.iter_many(world, &descendants).collect(),
+            invert: false,
+        }
+    }
+
+    fn children(&self, entity: Entity) -> &[Entity] {
+        self.world
+            .get::<Children>(entity)
+            .map_or(&[], |children| &**children)
+    }
+
+    /// The children of the entity, without despawned entities which are still listed.
+    fn live_children(&self, entity: Entity) -> impl Iterator<Item = Entity> + '_ {
+        self.children(entity)
+            .iter()
+            .copied()
+            .filter(|child| self.world.get_entity(*child).is_some())
+    }
+
+    fn depth_of(&self, entity: Entity) -> usize {
+        self.live_children(entity)
+            .map(|child| self.depth_of(child) + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn collect_descendants(&self, entity: Entity, out: &mut Vec<Entity>) {
+        for child in self.children(entity) {
+            out.push(*child);
+            self.collect_descendants(*child, out);
+        }
+    }
+
+    fn fail(&self, message: &str) -> ! {
+        print_failure(message);
+        eprintln!("{}", "Hierarchy:".bright_black());
+        eprintln!("{}", self.tree());
+        panic!("assertion failed");
+    }
+
+    /// Renders the tree below the root, one entity per line.
+    fn tree(&self) -> String {
+        let mut out = String::new();
+        self.write_tree(self.root, "", "", &mut out);
+        out.truncate(out.trim_end().len());
+        out
+    }
+
+    fn write_tree(&self, entity: Entity, prefix: &str, indent: &str, out: &mut String) {
+        *out += &format!("{prefix}{}\n", self.label(entity));
+        let children = self.children(entity);
+        for (i, child) in children.iter().enumerate() {
+            let (prefix, next) = if i + 1 == children.len() {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            self.write_tree(
+                *child,
+                &format!("{indent}{prefix}"),
+                &format!("{indent}{next}"),
+                out,
+            );
+        }
+    }
+
+    /// The name and id of the entity, followed by its components.
+    fn label(&self, entity: Entity) -> String {
+        let id = format!("{}v{}", entity.index(), entity.generation());
+        let Some(entity_ref) = self.world.get_entity(entity) else {
+            return format!("{id} {}", "(despawned)".bright_black());
+        };
+        let components = self
+            .world
+            .inspect_entity(entity)
+            .into_iter()
+            .map(|info| get_short_name(info.name()))
+            .filter(|name| !matches!(name.as_str(), "Name" | "Parent" | "Children"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let name = match entity_ref.get::<Name>() {
+            Some(name) => format!("\"{name}\" {}", format!("({id})").bright_black()),
+            None => id,
+        };
+        if components.is_empty() {
+            return name;
+        }
+        format!("{name} {}", format!("[{components}]").bright_black())
+    }
+
+    fn reset_invert(mut self) -> Self {
+        self.invert = false;
+        self
+    }
+}
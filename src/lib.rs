@@ -71,6 +71,17 @@
 //! `.length()`      | if the query matches the given length
 //! `.not()` ...     | to invert the test
 //!
+//! ## Features
+//!
+//! feature | description
+//! --------|--
+//! `scene` | `App::spawn_scene_file()` to spawn fixtures from scene files
+//! `harness` | the `harness` module with a test harness writing JUnit and JSON reports
+//! `proptest` | the `prop` module with strategies and helpers for property based testing
+//! `rand` | `RngCore` and `SeedableRng` of `rand_core` for `TestRng`
+//! `runner` | `ScenarioRunner` to run tests written as RON scenario files
+//! `session` | `SessionRecorder` and `App::replay()` to turn recorded sessions into tests
+//!
 //! ## Bevy versions
 //!
 //! bevy   | bevy_testing
@@ -78,20 +89,95 @@
 //! `0.14` | `0.1.1`
 //!
 
+// shadows the std macros in all modules, so that failures can be silenced via `quietly`
+macro_rules! eprintln {
+    ($($arg:tt)*) => {
+        if !$crate::is_quiet() {
+            ::std::eprintln!($($arg)*);
+        }
+    };
+}
+
+macro_rules! eprint {
+    ($($arg:tt)*) => {
+        if !$crate::is_quiet() {
+            ::std::eprint!($($arg)*);
+        }
+    };
+}
+
+mod ambiguity;
+mod change;
+mod condition;
+mod determinism;
+mod fixture;
+mod fuzz;
+mod golden;
+#[cfg(feature = "harness")]
+pub mod harness;
+mod hash;
+mod hierarchy;
+mod lifecycle;
+mod observer;
+#[cfg(feature = "proptest")]
+pub mod prop;
 mod query;
+mod recorder;
+mod rng;
+#[cfg(feature = "runner")]
+mod runner;
+mod scenario;
+mod schedule;
+#[cfg(feature = "session")]
+mod session;
+mod snapshot;
+mod spy;
+mod trace;
 
-use std::{any::type_name, fmt::Debug};
+pub use bevy_testing_macros::bevy_test;
+pub use determinism::Determinism;
+pub use fixture::{NamedEntities, SpawnTree};
+pub use fuzz::FuzzSystemOrder;
+pub use rng::TestRng;
+#[cfg(feature = "runner")]
+pub use runner::ScenarioRunner;
+pub use scenario::{scenario, Scenario, ScenarioTable};
+#[cfg(feature = "session")]
+pub use session::SessionRecorder;
+pub use snapshot::{SnapshotFilter, ValueChange, WorldDiff, WorldSnapshot};
+pub use trace::{Trace, TraceData, TraceValue};
 
+use std::{
+    any::{type_name, TypeId},
+    cell::Cell,
+    fmt::Debug,
+    marker::PhantomData,
+};
+
+use ambiguity::AllowedAmbiguities;
 use bevy::{
     ecs::{
+        observer::TriggerTargets,
         query::{QueryFilter, ReadOnlyQueryData},
+        schedule::ScheduleLabel,
+        system::RunSystemOnce,
         world::SpawnBatchIter,
     },
     prelude::*,
+    utils::get_short_name,
 };
+use change::{collect_changes, AssertChanged, ChangeKind};
 use colored::Colorize;
+use condition::AssertCondition;
+use hierarchy::AssertHierarchy;
+use lifecycle::{record_add, record_insert, record_remove, AssertLifecycle, LifecycleLog};
+use observer::{record_trigger, AssertTriggers, TriggerSpy};
 use query::AssertQuery;
+use recorder::Recorder;
+use schedule::AssertSchedule;
 use sealed::sealed;
+use spy::AssertSystems;
+use trace::Tracer;
 
 #[sealed]
 pub trait TestApp {
@@ -500,141 +586,1436 @@ pub trait TestApp {
     ///     .matches(vec![&Countdown(8)]);
     /// ```
     fn update_n_times(&mut self, amount: u32);
-}
 
-#[sealed]
-impl TestApp for App {
-    fn spawn_empty(&mut self) -> EntityWorldMut {
-        self.world_mut().spawn_empty()
-    }
+    /// Triggers the given `event`, which will run any observers watching for it.
+    /// If you want to trigger the event for specific entities, use [`App::trigger_targets`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Event)]
+    /// struct Explosion;
+    ///
+    /// #[derive(Resource)]
+    /// struct Explosions(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.insert_resource(Explosions(0));
+    /// app.observe(|_: Trigger<Explosion>, mut explosions: ResMut<Explosions>| {
+    ///     explosions.0 += 1;
+    /// });
+    ///
+    /// app.trigger(Explosion);
+    /// assert_eq!(app.world().resource::<Explosions>().0, 1);
+    /// ```
+    fn trigger(&mut self, event: impl Event);
 
-    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityWorldMut {
-        self.world_mut().spawn(bundle)
-    }
+    /// Triggers the given `event` for the given `targets`, which will run any observers watching for it.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Event)]
+    /// struct Damage(u32);
+    ///
+    /// #[derive(Component, Debug, PartialEq)]
+    /// struct Health(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.observe(|trigger: Trigger<Damage>, mut query: Query<&mut Health>| {
+    ///     let mut health = query.get_mut(trigger.entity()).unwrap();
+    ///     health.0 -= trigger.event().0;
+    /// });
+    ///
+    /// let player = app.spawn(Health(10)).id();
+    /// app.trigger_targets(Damage(3), player);
+    ///
+    /// app.query::<&Health>()
+    ///     .matches(vec![&Health(7)]);
+    /// ```
+    fn trigger_targets(&mut self, event: impl Event, targets: impl TriggerTargets);
 
-    fn spawn_batch<I>(&mut self, iter: I) -> SpawnBatchIter<'_, I::IntoIter>
-    where
-        I: IntoIterator,
-        I::Item: Bundle,
-    {
-        self.world_mut().spawn_batch(iter)
-    }
+    /// Installs an observer which records every trigger of the event `E` along with its target.
+    /// Use [`App::triggers`] to perform tests on the recorded triggers.
+    ///
+    /// Only triggers which happen after this call are recorded.
+    /// Calling this multiple times has no further effect.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Event)]
+    /// struct Damage(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.trigger(Damage(1)); // not recorded
+    ///
+    /// app.spy_triggers::<Damage>();
+    /// app.trigger(Damage(2));
+    ///
+    /// app.triggers::<Damage>()
+    ///     .count(1);
+    /// ```
+    fn spy_triggers<E: Event>(&mut self);
 
-    fn entity(&self, entity: Entity) -> EntityRef {
-        self.world().entity(entity)
-    }
+    /// Returns an [`AssertTriggers`] which can be used to perform tests on the triggers of
+    /// the event `E`. To invert the test, use [`AssertTriggers::not`].
+    ///
+    /// Panics if [`App::spy_triggers`] wasn't called for `E` beforehand.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Event)]
+    /// struct Damage(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.spy_triggers::<Damage>();
+    ///
+    /// let enemy = app.spawn_empty().id();
+    /// app.trigger_targets(Damage(5), enemy);
+    /// app.trigger_targets(Damage(3), enemy);
+    ///
+    /// app.triggers::<Damage>()
+    ///     .targeted(enemy)
+    ///     .count(2);
+    /// ```
+    fn triggers<E: Event>(&self) -> AssertTriggers<E>;
 
-    fn entity_mut(&mut self, entity: Entity) -> EntityWorldMut {
-        self.world_mut().entity_mut(entity)
-    }
+    /// Installs observers which record whenever the component `T` gets added, inserted or removed.
+    /// Use [`App::lifecycle`] to perform tests on the recorded lifecycle.
+    ///
+    /// Only changes which happen after this call are recorded.
+    /// Calling this multiple times has no further effect.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Stunned;
+    ///
+    /// fn wake_up(mut commands: Commands, query: Query<Entity, With<Stunned>>) {
+    ///     for entity in &query {
+    ///         commands.entity(entity).remove::<Stunned>();
+    ///     }
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(Update, wake_up);
+    /// app.track_lifecycle::<Stunned>();
+    ///
+    /// let enemy = app.spawn(Stunned).id();
+    /// app.update_once();
+    ///
+    /// app.lifecycle::<Stunned>()
+    ///     .added(enemy)
+    ///     .removed(enemy);
+    /// ```
+    fn track_lifecycle<T: Component>(&mut self);
 
-    fn get_entity(&self, entity: Entity) -> Option<EntityRef> {
-        self.world().get_entity(entity)
-    }
+    /// Returns an [`AssertLifecycle`] which can be used to perform tests on the lifecycle of
+    /// the component `T`. To invert the test, use [`AssertLifecycle::not`].
+    ///
+    /// Panics if [`App::track_lifecycle`] wasn't called for `T` beforehand.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.track_lifecycle::<Health>();
+    ///
+    /// let enemy = app.spawn(Health(10)).id();
+    /// app.entity_mut(enemy).insert(Health(5));
+    ///
+    /// app.lifecycle::<Health>()
+    ///     .added(enemy)
+    ///     .replaced(enemy)
+    ///     .inserted_times(enemy, 2)
+    ///     .not().removed(enemy);
+    /// ```
+    fn lifecycle<T: Component>(&self) -> AssertLifecycle<T>;
 
-    fn get_entity_mut(&mut self, entity: Entity) -> Option<EntityWorldMut> {
-        self.world_mut().get_entity_mut(entity)
-    }
+    /// Installs a spy which records the systems of all schedules that run during every frame,
+    /// including whether their run conditions let them run and in which order.
+    /// Use [`App::systems`] to perform tests on the recorded runs.
+    ///
+    /// Only frames which start after this call are recorded.
+    /// Calling this multiple times has no further effect.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// fn physics() {}
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(Update, physics);
+    /// app.update_once(); // not recorded
+    ///
+    /// app.spy_systems();
+    /// app.update_once();
+    ///
+    /// app.systems()
+    ///     .ran(physics, 1);
+    /// ```
+    fn spy_systems(&mut self);
 
-    fn component<T: Component>(&self, entity: Entity) -> &T {
-        self.get_component(entity).unwrap_or_else(|| {
-            panic!(
-                "component \"{}\" is not part of the entity",
-                type_name::<T>()
-            )
-        })
-    }
+    /// Returns an [`AssertSystems`] which can be used to perform tests on the systems which ran.
+    /// To invert the test, use [`AssertSystems::not`].
+    ///
+    /// Panics if [`App::spy_systems`] wasn't called beforehand.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// fn input() {}
+    /// fn physics() {}
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(Update, (input, physics.run_if(|| false)).chain());
+    /// app.spy_systems();
+    /// app.update_n_times(2);
+    ///
+    /// app.systems()
+    ///     .ran(input, 2)
+    ///     .did_not_run(physics);
+    /// ```
+    fn systems(&self) -> AssertSystems;
 
-    fn get_component<T: Component>(&self, entity: Entity) -> Option<&T> {
-        self.world().entity(entity).get::<T>()
-    }
+    /// Evaluates a run condition once against the current world, without running any system,
+    /// and returns an [`AssertCondition`] to perform tests on the result.
+    /// To invert the test, use [`AssertCondition::not`].
+    ///
+    /// The condition is a fresh instance, so change detection conditions like
+    /// `resource_changed` see everything as changed.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Resource)]
+    /// struct Paused(bool);
+    ///
+    /// let mut app = App::new();
+    /// app.insert_resource(Paused(true));
+    ///
+    /// app.condition(|paused: Res<Paused>| !paused.0)
+    ///     .evaluates_to(false);
+    /// ```
+    fn condition<M>(&mut self, condition: impl Condition<M>) -> AssertCondition;
 
-    fn query<'w, D: ReadOnlyQueryData>(&'w mut self) -> AssertQuery<'w, D>
-    where
-        D::Item<'w>: PartialEq + Debug,
-    {
-        let mut query = self.world_mut().query::<D>();
-        let collected = query.iter(self.world()).collect::<Vec<_>>();
-        AssertQuery {
-            query: collected,
-            invert: false,
-        }
-    }
+    /// Checks if the system is part of the schedule, but didn't run during the last frame
+    /// because one of its run conditions or those of its sets was `false`, and prints which one.
+    /// If it did run, every condition is printed along with its value.
+    /// Use [`App::condition`] to test the conditions themselves.
+    ///
+    /// Bevy doesn't expose the conditions of a built schedule, so their values are inferred
+    /// from the systems which ran: the conditions of a set were `true` if any system in it ran.
+    /// If that leaves more than one condition which may have been `false`, all of them are printed.
+    ///
+    /// Panics if [`App::spy_systems`] wasn't called before the schedule first ran.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Resource)]
+    /// struct Paused(bool);
+    ///
+    /// fn physics() {}
+    ///
+    /// let mut app = App::new();
+    /// app.insert_resource(Paused(true));
+    /// app.add_systems(Update, physics.run_if(|paused: Res<Paused>| !paused.0));
+    /// app.spy_systems();
+    /// app.update_once();
+    ///
+    /// app.assert_gated(physics, Update);
+    /// ```
+    fn assert_gated<M>(&self, system: impl IntoSystem<(), (), M>, schedule: impl ScheduleLabel);
 
-    fn query_filtered<'w, D: ReadOnlyQueryData, F: QueryFilter>(&'w mut self) -> AssertQuery<'w, D>
-    where
-        D::Item<'w>: PartialEq + Debug,
-    {
-        let mut query = self.world_mut().query_filtered::<D, F>();
-        let collected = query.iter(self.world()).collect::<Vec<_>>();
-        AssertQuery {
-            query: collected,
-            invert: false,
-        }
-    }
+    /// Builds the schedule, if it wasn't built yet, and returns an [`AssertSchedule`]
+    /// to perform tests on its systems, ordering constraints and sets.
+    /// To invert the test, use [`AssertSchedule::not`].
+    ///
+    /// Panics if the schedule doesn't exist or can't be built, e.g. because of a cycle.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// fn input() {}
+    /// fn physics() {}
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(Update, (input, physics).chain());
+    ///
+    /// app.schedule(Update)
+    ///     .contains(physics)
+    ///     .orders(input, physics);
+    /// ```
+    fn schedule(&mut self, label: impl ScheduleLabel) -> AssertSchedule<'_>;
 
-    fn update_once(&mut self) {
-        self.update();
-    }
+    /// Builds the schedule and checks that no two systems with conflicting data access,
+    /// e.g. both writing the same component, can run in either order.
+    /// On failure, the conflicting pairs are listed along with the components and resources
+    /// they conflict on.
+    ///
+    /// Ambiguities can be resolved by ordering the systems, or allowed via
+    /// [`App::allow_ambiguity`], [`App::allow_ambiguous_component`] and
+    /// [`App::allow_ambiguous_resource`].
+    /// The schedule's [`ScheduleBuildSettings`](bevy::ecs::schedule::ScheduleBuildSettings)
+    /// are left as they were, so this works regardless of its ambiguity detection level.
+    ///
+    /// Panics if the schedule doesn't exist or can't be built, e.g. because of a cycle.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Resource, Default)]
+    /// struct Score(u32);
+    ///
+    /// fn add_points(mut score: ResMut<Score>) {}
+    /// fn apply_bonus(mut score: ResMut<Score>) {}
+    ///
+    /// let mut app = App::new();
+    /// app.init_resource::<Score>();
+    /// app.add_systems(Update, (add_points, apply_bonus.after(add_points)));
+    ///
+    /// app.assert_no_ambiguities(Update);
+    /// ```
+    fn assert_no_ambiguities(&mut self, schedule: impl ScheduleLabel);
 
-    fn update_n_times(&mut self, amount: u32) {
-        for _ in 0..amount {
-            self.update_once();
-        }
-    }
-}
+    /// Allows the two systems to run in either order, in every schedule checked by
+    /// [`App::assert_no_ambiguities`].
+    ///
+    /// Unlike [`IntoSystemConfigs::ambiguous_with`], this doesn't change the app itself.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Resource, Default)]
+    /// struct Log(Vec<&'static str>);
+    ///
+    /// fn log_input(mut log: ResMut<Log>) {}
+    /// fn log_physics(mut log: ResMut<Log>) {}
+    ///
+    /// let mut app = App::new();
+    /// app.init_resource::<Log>();
+    /// app.add_systems(Update, (log_input, log_physics));
+    ///
+    /// app.allow_ambiguity(log_input, log_physics);
+    /// app.assert_no_ambiguities(Update);
+    /// ```
+    fn allow_ambiguity<M1, M2>(
+        &mut self,
+        a: impl IntoSystem<(), (), M1>,
+        b: impl IntoSystem<(), (), M2>,
+    );
 
-const MAX_DEBUG_LEN: usize = 300;
+    /// Inserts a [`TestRng`] with the given seed, replacing the one installed by
+    /// [`bevy_test`](crate::bevy_test), so that systems using it behave the same on every run.
+    ///
+    /// The `BEVY_TESTING_SEED` environment variable overrides the seed.
+    /// Failing assertions print the seed, so a failure can be reproduced via the variable.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    /// use bevy_testing::TestRng;
+    ///
+    /// let roll = |app: &mut App| app.world_mut().resource_mut::<TestRng>().below(6);
+    ///
+    /// let mut a = App::new();
+    /// a.seed(42);
+    /// let mut b = App::new();
+    /// b.seed(42);
+    ///
+    /// assert_eq!(roll(&mut a), roll(&mut b));
+    /// ```
+    fn seed(&mut self, seed: u64);
 
-fn mismatch(message: &str, given: impl Debug, found: impl Debug) -> ! {
-    let mut given = format!("{:#?}", given);
-    if given.len() > MAX_DEBUG_LEN {
-        given = given[0..MAX_DEBUG_LEN].to_owned() + &" ...".bright_black();
-    }
-    let mut found = format!("{:#?}", found);
-    if found.len() > MAX_DEBUG_LEN {
-        found = found[0..MAX_DEBUG_LEN].to_owned() + &" ...".bright_black();
-    }
-    eprintln!("{}", message.red());
-    if given.contains('\n') {
-        eprintln!("{}", "Given:".bright_black());
-        eprintln!("{}", given);
-    } else {
-        eprintln!("{} {}", "Given:".bright_black(), given);
-    }
-    eprintln!();
-    if found.contains('\n') {
-        eprintln!("{}", "Found:".bright_black());
-        eprintln!("{}", found);
-    } else {
-        eprintln!("{} {}", "Found:".bright_black(), found);
-    }
-    panic!("assertion failed");
-}
+    /// Returns an [`AssertChanged`] which can be used to perform tests on the entities whose
+    /// component `T` was changed since the last call of this method for `T`.
+    /// On the first call, every change since the creation of the world counts.
+    /// To invert the test, use [`AssertChanged::not`].
+    ///
+    /// This is useful to prove that systems don't mutate components unnecessarily,
+    /// which would trigger any work depending on [`Changed`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// fn clamp_health(mut query: Query<&mut Health>) {
+    ///     for mut health in &mut query {
+    ///         if health.0 > 100 {
+    ///             health.0 = 100;
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(Update, clamp_health);
+    /// let overhealed = app.spawn(Health(120)).id();
+    /// app.spawn(Health(50));
+    /// app.changed::<Health>();
+    ///
+    /// app.update_once();
+    /// app.changed::<Health>()
+    ///     .only(overhealed);
+    ///
+    /// app.update_once();
+    /// app.changed::<Health>()
+    ///     .none();
+    /// ```
+    fn changed<T: Component>(&mut self) -> AssertChanged<T>;
 
-fn unexpected_match(message: &str, matches: impl Debug) -> ! {
-    let mut given = format!("{:#?}", matches);
-    if given.len() > MAX_DEBUG_LEN {
-        given = given[0..MAX_DEBUG_LEN].to_owned() + &" ...".bright_black();
-    }
-    eprintln!("{}", message.red());
-    if given.contains('\n') {
-        eprintln!("{}", "Match:".bright_black());
-        eprintln!("{}", given);
-    } else {
-        eprintln!("{} {}", "Match:".bright_black(), given);
+    /// Returns an [`AssertChanged`] which can be used to perform tests on the entities whose
+    /// component `T` was added since the last call of this method for `T`.
+    /// On the first call, every addition since the creation of the world counts.
+    /// To invert the test, use [`AssertChanged::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// #[derive(Component)]
+    /// struct Dead;
+    ///
+    /// fn die(mut commands: Commands, query: Query<(Entity, &Health), Without<Dead>>) {
+    ///     for (entity, health) in &query {
+    ///         if health.0 == 0 {
+    ///             commands.entity(entity).insert(Dead);
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(Update, die);
+    /// let dead = app.spawn(Health(0)).id();
+    /// app.spawn(Health(10));
+    ///
+    /// app.update_once();
+    /// app.added::<Dead>()
+    ///     .only(dead);
+    /// ```
+    fn added<T: Component>(&mut self) -> AssertChanged<T>;
+
+    /// Updates the app once and checks that the component `T` of the given `entity`
+    /// wasn't changed during that update.
+    /// Panics if the `entity` doesn't exist or doesn't have a component of type `T`.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// fn regenerate(mut query: Query<&mut Health>) {
+    ///     for mut health in &mut query {
+    ///         if health.0 < 10 {
+    ///             health.0 += 1;
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(Update, regenerate);
+    /// let healthy = app.spawn(Health(10)).id();
+    ///
+    /// app.assert_unchanged::<Health>(healthy);
+    /// ```
+    fn assert_unchanged<T: Component>(&mut self, entity: Entity);
+
+    /// Captures a [`WorldSnapshot`] of all reflectable components and resources.
+    /// Use [`App::diff_since`] to find out what changed since the snapshot was taken.
+    ///
+    /// Only types which are registered via [`App::register_type`] and reflect
+    /// [`Component`] or [`Resource`] are captured.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component, Reflect)]
+    /// #[reflect(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.register_type::<Health>();
+    /// app.spawn(Health(10));
+    ///
+    /// let snapshot = app.snapshot();
+    /// assert_eq!(snapshot.entity_count(), 1);
+    /// ```
+    fn snapshot(&self) -> WorldSnapshot;
+
+    /// Returns a [`WorldDiff`] listing all spawned and despawned entities, added and removed
+    /// components and changed field values since the given `snapshot` was taken.
+    /// The diff can be printed via its [`Display`](std::fmt::Display) implementation.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component, Reflect)]
+    /// #[reflect(Component)]
+    /// struct Position {
+    ///     x: f32,
+    ///     y: f32,
+    /// }
+    ///
+    /// #[derive(Component, Reflect)]
+    /// #[reflect(Component)]
+    /// struct Stunned;
+    ///
+    /// fn fall(mut commands: Commands, mut query: Query<(Entity, &mut Position)>) {
+    ///     for (entity, mut position) in &mut query {
+    ///         position.y -= 1.0;
+    ///         commands.entity(entity).insert(Stunned);
+    ///     }
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.register_type::<Position>();
+    /// app.register_type::<Stunned>();
+    /// app.add_systems(Update, fall);
+    /// let player = app.spawn((Name::new("player"), Position { x: 0.0, y: 2.0 })).id();
+    ///
+    /// let snapshot = app.snapshot();
+    /// app.update_once();
+    ///
+    /// let diff = app.diff_since(&snapshot);
+    /// println!("{diff}");
+    /// assert_eq!(diff.changed.len(), 1);
+    /// assert_eq!(diff.changed[0].field, "y");
+    /// assert_eq!(diff.added[0].0, player);
+    /// ```
+    fn diff_since(&self, snapshot: &WorldSnapshot) -> WorldDiff;
+
+    /// Captures a [`WorldSnapshot`] of the components and resources chosen by the `filter`.
+    /// If you want to capture everything, use [`App::snapshot`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    /// use bevy_testing::SnapshotFilter;
+    ///
+    /// #[derive(Component, Reflect)]
+    /// #[reflect(Component)]
+    /// struct Health(u32);
+    ///
+    /// #[derive(Resource, Reflect)]
+    /// #[reflect(Resource)]
+    /// struct Score(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.register_type::<Health>();
+    /// app.register_type::<Score>();
+    /// app.insert_resource(Score(0));
+    /// app.spawn(Health(10));
+    ///
+    /// let snapshot = app.snapshot_with(&SnapshotFilter::new().resource::<Score>());
+    /// assert_eq!(snapshot.entity_count(), 0);
+    /// ```
+    fn snapshot_with(&self, filter: &SnapshotFilter) -> WorldSnapshot;
+
+    /// Compares all reflectable components and resources to the snapshot file
+    /// `tests/snapshots/<name>.ron` and panics with a diff if they changed.
+    /// To only compare some components and resources, use [`App::assert_snapshot_with`].
+    ///
+    /// If the file doesn't exist yet, it gets created, unless the `CI` environment variable is set.
+    /// Set the `BEVY_TESTING_UPDATE` environment variable to `1` to accept changed snapshots.
+    ///
+    /// See [`WorldSnapshot::to_ron`] for how the world is written to the file.
+    ///
+    /// ```no_run
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component, Reflect)]
+    /// #[reflect(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.register_type::<Health>();
+    /// app.spawn((Name::new("player"), Health(10)));
+    /// app.update_once();
+    ///
+    /// app.assert_snapshot("player_spawned");
+    /// ```
+    fn assert_snapshot(&self, name: &str);
+
+    /// Compares the components and resources chosen by the `filter` to the snapshot file
+    /// `tests/snapshots/<name>.ron` and panics with a diff if they changed.
+    /// This works just like [`App::assert_snapshot`].
+    ///
+    /// ```no_run
+    /// use bevy_testing::p::*;
+    /// use bevy_testing::SnapshotFilter;
+    ///
+    /// #[derive(Component, Reflect)]
+    /// #[reflect(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.register_type::<Health>();
+    /// app.spawn((Name::new("player"), Health(10)));
+    /// app.update_once();
+    ///
+    /// app.assert_snapshot_with("player_health", &SnapshotFilter::new().component::<Health>());
+    /// ```
+    fn assert_snapshot_with(&self, name: &str, filter: &SnapshotFilter);
+
+    /// Returns a hash of all reflectable components and resources, in the order of the entities.
+    ///
+    /// Values are hashed like they are written to snapshot files, with the entries of maps
+    /// sorted and entities replaced by their position, so equal worlds lead to equal hashes.
+    /// Sets are opaque to reflection, so their entries aren't part of the hash.
+    /// This makes it a cheap alternative to [`App::assert_snapshot`], or a way to check that
+    /// apps stay in lockstep. The [`Time`] resources are left out.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component, Reflect)]
+    /// #[reflect(Component)]
+    /// struct Health(u32);
+    ///
+    /// fn build() -> App {
+    ///     let mut app = App::new();
+    ///     app.register_type::<Health>();
+    ///     app.spawn(Health(10));
+    ///     app
+    /// }
+    ///
+    /// let (mut a, b) = (build(), build());
+    /// assert_eq!(a.world_hash(), b.world_hash());
+    ///
+    /// a.spawn(Health(5));
+    /// assert_ne!(a.world_hash(), b.world_hash());
+    /// ```
+    fn world_hash(&self) -> u64;
+
+    /// Asserts that [`App::world_hash`] returns `expected`.
+    ///
+    /// On a mismatch, the hash and count of every component and resource type get printed,
+    /// which narrows down the changed types when compared to the output of a passing run.
+    ///
+    /// ```should_panic
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component, Reflect)]
+    /// #[reflect(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.register_type::<Health>();
+    /// app.spawn(Health(10));
+    ///
+    /// let hash = app.world_hash();
+    /// app.assert_world_hash_eq(hash);
+    ///
+    /// app.spawn(Health(5));
+    /// app.assert_world_hash_eq(hash);
+    /// ```
+    fn assert_world_hash_eq(&self, expected: u64);
+
+    /// Updates the app `frames` times and samples a value after every update.
+    /// Returns a [`Recorder`] which can be used to perform temporal tests on the samples.
+    ///
+    /// If you want to record a resource, use [`App::record_resource`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(i32);
+    ///
+    /// fn poison(mut query: Query<&mut Health>) {
+    ///     for mut health in &mut query {
+    ///         health.0 -= 1;
+    ///     }
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(Update, poison);
+    /// let player = app.spawn(Health(10)).id();
+    ///
+    /// app.record(10, |world| world.get::<Health>(player).unwrap().0)
+    ///     .never(|health| *health < 0)
+    ///     .eventually_within(10, |health| *health == 0);
+    /// ```
+    fn record<V: Debug>(&mut self, frames: u32, sample: impl FnMut(&mut World) -> V)
+        -> Recorder<V>;
+
+    /// Updates the app `frames` times and clones the resource `R` after every update.
+    /// Returns a [`Recorder`] which can be used to perform temporal tests on the samples.
+    ///
+    /// Panics if the resource doesn't exist after an update.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Resource, Clone, Debug)]
+    /// struct Score(u32);
+    ///
+    /// fn score(mut score: ResMut<Score>) {
+    ///     score.0 += 10;
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.insert_resource(Score(0));
+    /// app.add_systems(Update, score);
+    ///
+    /// app.record_resource::<Score>(5)
+    ///     .monotonic_by_key(|score| score.0)
+    ///     .eventually_within(5, |score| score.0 == 50);
+    /// ```
+    fn record_resource<R: Resource + Clone + Debug>(&mut self, frames: u32) -> Recorder<R>;
+
+    /// Returns a [`Tracer`](trace::Tracer) which samples the query with the filter `F` while updating the app.
+    /// The resulting [`Trace`] can be written to CSV or JSON lines, e.g. to plot values over time.
+    ///
+    /// See [`TraceData`] for the query data which can be traced.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Player;
+    ///
+    /// #[derive(Component, Reflect)]
+    /// struct Velocity(Vec3);
+    ///
+    /// fn movement(mut query: Query<(&mut Transform, &Velocity)>) {
+    ///     for (mut transform, velocity) in &mut query {
+    ///         transform.translation += velocity.0;
+    ///     }
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(Update, movement);
+    /// app.spawn((Player, Transform::default(), Velocity(Vec3::X)));
+    /// app.spawn((Transform::default(), Velocity(Vec3::Y)));
+    ///
+    /// let trace = app
+    ///     .trace::<(&Transform, &Velocity), With<Player>>()
+    ///     .dump_on_panic()
+    ///     .frames(300);
+    /// assert_eq!(trace.len(), 301);
+    /// ```
+    fn trace<D: TraceData, F: QueryFilter>(&mut self) -> Tracer<'_, D, F>;
+
+    /// Returns an [`AssertHierarchy`] which can be used to perform tests on the entity tree
+    /// below `root`, built from [`Children`] and [`Parent`].
+    /// To invert the test, use [`AssertHierarchy::not`].
+    ///
+    /// Panics if `root` doesn't exist.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component, Debug, PartialEq)]
+    /// struct Bone(&'static str);
+    ///
+    /// let mut app = App::new();
+    /// let rig = app.spawn(Bone("hip")).id();
+    /// app.entity_mut(rig).with_children(|hip| {
+    ///     hip.spawn(Bone("spine")).with_children(|spine| {
+    ///         spine.spawn(Bone("head"));
+    ///     });
+    ///     hip.spawn(Bone("leg"));
+    /// });
+    ///
+    /// app.hierarchy(rig)
+    ///     .children_count(2)
+    ///     .depth(2)
+    ///     .descendants::<&Bone>()
+    ///     .has(&Bone("head"))
+    ///     .length(3);
+    /// ```
+    fn hierarchy(&mut self, root: Entity) -> AssertHierarchy<'_>;
+
+    /// Spawns an entity tree, usually declared via the [`tree!`] macro, and returns the
+    /// spawned entities by name. Every entity gets a [`Name`] component.
+    ///
+    /// Panics if multiple entities in the tree have the same name.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Button;
+    /// #[derive(Component, Debug, PartialEq)]
+    /// struct Text(&'static str);
+    ///
+    /// let mut app = App::new();
+    /// let ids = app.spawn_tree(tree! {
+    ///     "menu": () => [
+    ///         "play": Button => [ "play_text": Text("Play") ],
+    ///         "quit": Button => [ "quit_text": Text("Quit") ],
+    ///     ]
+    /// });
+    ///
+    /// app.hierarchy(ids["quit"])
+    ///     .descendants::<&Text>()
+    ///     .matches(vec![&Text("Quit")]);
+    /// ```
+    fn spawn_tree(&mut self, tree: SpawnTree) -> NamedEntities;
+
+    /// Deserializes the [`DynamicScene`](bevy::scene::DynamicScene) in the given RON file
+    /// using the app's [`AppTypeRegistry`] and spawns it, without needing an asset server.
+    /// Returns the spawned entities which have a [`Name`], by name.
+    ///
+    /// Relative paths are resolved from the working directory, which is the package root
+    /// when running `cargo test`. [`Name`], [`Parent`] and [`Children`] are registered
+    /// automatically, all other types in the scene need to be registered beforehand.
+    ///
+    /// Panics if the file can't be read or spawned, or if multiple entities have the same name.
+    ///
+    /// This requires the `scene` feature.
+    ///
+    /// ```no_run
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component, Reflect, Default)]
+    /// #[reflect(Component)]
+    /// struct Door {
+    ///     open: bool,
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.register_type::<Door>();
+    ///
+    /// let ids = app.spawn_scene_file("tests/fixtures/level1.scn.ron");
+    /// assert!(!app.component::<Door>(ids["front_door"]).open);
+    /// ```
+    #[cfg(feature = "scene")]
+    fn spawn_scene_file(&mut self, path: impl AsRef<std::path::Path>) -> NamedEntities;
+
+    /// Writes the session recorded by the [`SessionRecorder`] plugin to its file,
+    /// without waiting for the app to exit.
+    ///
+    /// Panics if the plugin wasn't added.
+    ///
+    /// This requires the `session` feature.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    /// use bevy_testing::SessionRecorder;
+    ///
+    /// let path = std::env::temp_dir().join("bevy_testing_save_session.ron");
+    ///
+    /// let mut app = App::new();
+    /// app.add_plugins(SessionRecorder::new(&path));
+    /// app.update_n_times(3);
+    /// app.save_session();
+    ///
+    /// assert!(std::fs::read_to_string(&path).unwrap().contains("frames"));
+    /// ```
+    #[cfg(feature = "session")]
+    fn save_session(&mut self);
+
+    /// Replays a session recorded by the [`SessionRecorder`] plugin: the [`TestRng`] gets the
+    /// recorded seed, and every update gets the recorded input, events and time delta.
+    /// Afterwards, the hash of the world is compared to the recorded one.
+    ///
+    /// The app has to be built like the recorded one and must not have been updated yet.
+    /// Recorded events can only be replayed once their type was added via [`App::replay_event`].
+    ///
+    /// This requires the `session` feature.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    /// use bevy_testing::SessionRecorder;
+    ///
+    /// #[derive(Event, Reflect)]
+    /// struct Spawn(u32);
+    ///
+    /// #[derive(Component, Reflect, Debug, PartialEq)]
+    /// #[reflect(Component)]
+    /// struct Enemy(u32);
+    ///
+    /// fn spawn(mut commands: Commands, mut events: EventReader<Spawn>) {
+    ///     for event in events.read() {
+    ///         commands.spawn(Enemy(event.0));
+    ///     }
+    /// }
+    ///
+    /// fn build() -> App {
+    ///     let mut app = App::new();
+    ///     app.register_type::<Enemy>();
+    ///     app.add_event::<Spawn>();
+    ///     app.add_systems(Update, spawn);
+    ///     app
+    /// }
+    ///
+    /// let path = std::env::temp_dir().join("bevy_testing_replay.ron");
+    ///
+    /// let mut app = build();
+    /// app.add_plugins(SessionRecorder::new(&path).event::<Spawn>());
+    /// app.world_mut().send_event(Spawn(3));
+    /// app.update_n_times(2);
+    /// app.save_session();
+    ///
+    /// let mut replay = build();
+    /// replay.replay_event::<Spawn>();
+    /// replay.replay(&path);
+    /// replay.query::<&Enemy>().matches(vec![&Enemy(3)]);
+    /// ```
+    #[cfg(feature = "session")]
+    fn replay(&mut self, path: impl AsRef<std::path::Path>);
+
+    /// Adds the event type `E`, so that recorded events of it can be replayed via [`App::replay`].
+    ///
+    /// This requires the `session` feature.
+    #[cfg(feature = "session")]
+    fn replay_event<
+        E: Event + FromReflect + bevy::reflect::TypePath + bevy::reflect::GetTypeRegistration,
+    >(
+        &mut self,
+    );
+}
+
+#[sealed]
+impl TestApp for App {
+    fn spawn_empty(&mut self) -> EntityWorldMut {
+        self.world_mut().spawn_empty()
     }
+
+    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityWorldMut {
+        self.world_mut().spawn(bundle)
+    }
+
+    fn spawn_batch<I>(&mut self, iter: I) -> SpawnBatchIter<'_, I::IntoIter>
+    where
+        I: IntoIterator,
+        I::Item: Bundle,
+    {
+        self.world_mut().spawn_batch(iter)
+    }
+
+    fn entity(&self, entity: Entity) -> EntityRef {
+        self.world().entity(entity)
+    }
+
+    fn entity_mut(&mut self, entity: Entity) -> EntityWorldMut {
+        self.world_mut().entity_mut(entity)
+    }
+
+    fn get_entity(&self, entity: Entity) -> Option<EntityRef> {
+        self.world().get_entity(entity)
+    }
+
+    fn get_entity_mut(&mut self, entity: Entity) -> Option<EntityWorldMut> {
+        self.world_mut().get_entity_mut(entity)
+    }
+
+    fn component<T: Component>(&self, entity: Entity) -> &T {
+        self.get_component(entity).unwrap_or_else(|| {
+            panic!(
+                "component \"{}\" is not part of the entity",
+                type_name::<T>()
+            )
+        })
+    }
+
+    fn get_component<T: Component>(&self, entity: Entity) -> Option<&T> {
+        self.world().entity(entity).get::<T>()
+    }
+
+    fn query<'w, D: ReadOnlyQueryData>(&'w mut self) -> AssertQuery<'w, D>
+    where
+        D::Item<'w>: PartialEq + Debug,
+    {
+        let mut query = self.world_mut().query::<D>();
+        let collected = query.iter(self.world()).collect::<Vec<_>>();
+        AssertQuery {
+            query: collected,
+            invert: false,
+        }
+    }
+
+    fn query_filtered<'w, D: ReadOnlyQueryData, F: QueryFilter>(&'w mut self) -> AssertQuery<'w, D>
+    where
+        D::Item<'w>: PartialEq + Debug,
+    {
+        let mut query = self.world_mut().query_filtered::<D, F>();
+        let collected = query.iter(self.world()).collect::<Vec<_>>();
+        AssertQuery {
+            query: collected,
+            invert: false,
+        }
+    }
+
+    fn update_once(&mut self) {
+        self.update();
+        #[cfg(feature = "harness")]
+        harness::count_frame();
+    }
+
+    fn update_n_times(&mut self, amount: u32) {
+        for _ in 0..amount {
+            self.update_once();
+        }
+    }
+
+    fn trigger(&mut self, event: impl Event) {
+        // observers only get registered once the world is flushed
+        self.world_mut().flush();
+        self.world_mut().trigger(event);
+    }
+
+    fn trigger_targets(&mut self, event: impl Event, targets: impl TriggerTargets) {
+        self.world_mut().flush();
+        self.world_mut().trigger_targets(event, targets);
+    }
+
+    fn spy_triggers<E: Event>(&mut self) {
+        if self.world().contains_resource::<TriggerSpy<E>>() {
+            return;
+        }
+        self.init_resource::<TriggerSpy<E>>();
+        self.observe(record_trigger::<E>);
+        self.world_mut().flush();
+    }
+
+    fn triggers<E: Event>(&self) -> AssertTriggers<E> {
+        let spy = self
+            .world()
+            .get_resource::<TriggerSpy<E>>()
+            .unwrap_or_else(|| {
+                panic!(
+                    "triggers of \"{}\" are not recorded, call `App::spy_triggers` first",
+                    type_name::<E>()
+                )
+            });
+        AssertTriggers {
+            targets: spy.targets.clone(),
+            invert: false,
+            marker: PhantomData,
+        }
+    }
+
+    fn track_lifecycle<T: Component>(&mut self) {
+        if self.world().contains_resource::<LifecycleLog<T>>() {
+            return;
+        }
+        self.init_resource::<LifecycleLog<T>>();
+        self.observe(record_add::<T>);
+        self.observe(record_insert::<T>);
+        self.observe(record_remove::<T>);
+        self.world_mut().flush();
+    }
+
+    fn lifecycle<T: Component>(&self) -> AssertLifecycle<T> {
+        let log = self
+            .world()
+            .get_resource::<LifecycleLog<T>>()
+            .unwrap_or_else(|| {
+                panic!(
+                    "the lifecycle of \"{}\" is not tracked, call `App::track_lifecycle` first",
+                    type_name::<T>()
+                )
+            });
+        AssertLifecycle {
+            events: log.events.clone(),
+            invert: false,
+            marker: PhantomData,
+        }
+    }
+
+    fn spy_systems(&mut self) {
+        spy::spy_systems(self);
+    }
+
+    fn systems(&self) -> AssertSystems {
+        let spy = self
+            .world()
+            .get_resource::<spy::SystemSpy>()
+            .unwrap_or_else(|| panic!("systems are not recorded, call `App::spy_systems` first"));
+        AssertSystems {
+            frames: spy.frames.clone(),
+            invert: false,
+        }
+    }
+
+    fn condition<M>(&mut self, condition: impl Condition<M>) -> AssertCondition {
+        let condition = IntoSystem::into_system(condition);
+        let name = get_short_name(&condition.name());
+        AssertCondition {
+            name,
+            value: self.world_mut().run_system_once(condition),
+            invert: false,
+        }
+    }
+
+    fn assert_gated<M>(&self, system: impl IntoSystem<(), (), M>, schedule: impl ScheduleLabel) {
+        let (type_id, name) = system_info(system);
+        spy::assert_gated(self.world(), type_id, &name, schedule.intern());
+    }
+
+    fn schedule(&mut self, label: impl ScheduleLabel) -> AssertSchedule<'_> {
+        let label = label.intern();
+        let world = self.world_mut();
+        world
+            .try_schedule_scope(label, |world, schedule| schedule.initialize(world))
+            .unwrap_or_else(|_| panic!("the schedule {label:?} doesn't exist"))
+            .unwrap_or_else(|err| panic!("the schedule {label:?} can't be built: {err}"));
+        AssertSchedule {
+            schedule: world.resource::<Schedules>().get(label).unwrap(),
+            invert: false,
+        }
+    }
+
+    fn assert_no_ambiguities(&mut self, schedule: impl ScheduleLabel) {
+        ambiguity::assert_no_ambiguities(self.world_mut(), schedule.intern());
+    }
+
+    fn allow_ambiguity<M1, M2>(
+        &mut self,
+        a: impl IntoSystem<(), (), M1>,
+        b: impl IntoSystem<(), (), M2>,
+    ) {
+        let pair = (a.system_type_id(), b.system_type_id());
+        self.world_mut()
+            .get_resource_or_insert_with(AllowedAmbiguities::default)
+            .0
+            .push(pair);
+    }
+
+    fn seed(&mut self, seed: u64) {
+        let seed = rng::env_seed().unwrap_or(seed);
+        rng::set_seed(seed);
+        self.insert_resource(TestRng::new(seed));
+    }
+
+    fn changed<T: Component>(&mut self) -> AssertChanged<T> {
+        AssertChanged {
+            entities: collect_changes::<T>(self.world_mut(), ChangeKind::Changed),
+            kind: ChangeKind::Changed,
+            invert: false,
+            marker: PhantomData,
+        }
+    }
+
+    fn added<T: Component>(&mut self) -> AssertChanged<T> {
+        AssertChanged {
+            entities: collect_changes::<T>(self.world_mut(), ChangeKind::Added),
+            kind: ChangeKind::Added,
+            invert: false,
+            marker: PhantomData,
+        }
+    }
+
+    fn assert_unchanged<T: Component>(&mut self, entity: Entity) {
+        let last_run = self.world_mut().change_tick();
+        self.update_once();
+
+        let this_run = self.world().read_change_tick();
+        let ticks = self
+            .entity(entity)
+            .get_change_ticks::<T>()
+            .unwrap_or_else(|| {
+                panic!(
+                    "component \"{}\" is not part of the entity",
+                    type_name::<T>()
+                )
+            });
+        if ticks.is_changed(last_run, this_run) {
+            unexpected_match(
+                &format!(
+                    "The component \"{}\" was changed during the update.",
+                    type_name::<T>()
+                ),
+                entity,
+            );
+        }
+    }
+
+    fn snapshot(&self) -> WorldSnapshot {
+        self.snapshot_with(&SnapshotFilter::all())
+    }
+
+    fn diff_since(&self, snapshot: &WorldSnapshot) -> WorldDiff {
+        snapshot.diff(&self.snapshot())
+    }
+
+    fn snapshot_with(&self, filter: &SnapshotFilter) -> WorldSnapshot {
+        WorldSnapshot::capture(self.world(), filter)
+    }
+
+    fn assert_snapshot(&self, name: &str) {
+        self.assert_snapshot_with(name, &SnapshotFilter::all());
+    }
+
+    fn assert_snapshot_with(&self, name: &str, filter: &SnapshotFilter) {
+        let ron = self
+            .snapshot_with(filter)
+            .to_ron(self.world().resource::<AppTypeRegistry>());
+        golden::assert_golden(name, &ron);
+    }
+
+    fn world_hash(&self) -> u64 {
+        hash::world_hash(self.world())
+    }
+
+    fn assert_world_hash_eq(&self, expected: u64) {
+        hash::assert_world_hash_eq(self.world(), expected);
+    }
+
+    fn record<V: Debug>(
+        &mut self,
+        frames: u32,
+        mut sample: impl FnMut(&mut World) -> V,
+    ) -> Recorder<V> {
+        let frames = (0..frames)
+            .map(|_| {
+                self.update_once();
+                sample(self.world_mut())
+            })
+            .collect();
+        Recorder { frames }
+    }
+
+    fn record_resource<R: Resource + Clone + Debug>(&mut self, frames: u32) -> Recorder<R> {
+        self.record(frames, |world| {
+            world
+                .get_resource::<R>()
+                .unwrap_or_else(|| panic!("the resource \"{}\" doesn't exist", type_name::<R>()))
+                .clone()
+        })
+    }
+
+    fn trace<D: TraceData, F: QueryFilter>(&mut self) -> Tracer<'_, D, F> {
+        Tracer {
+            app: self,
+            dump_on_panic: false,
+            marker: PhantomData,
+        }
+    }
+
+    fn hierarchy(&mut self, root: Entity) -> AssertHierarchy<'_> {
+        self.world().entity(root);
+        AssertHierarchy {
+            world: self.world_mut(),
+            root,
+            invert: false,
+        }
+    }
+
+    fn spawn_tree(&mut self, tree: SpawnTree) -> NamedEntities {
+        let mut ids = NamedEntities::default();
+        tree.spawn(self.world_mut(), &mut ids);
+        ids
+    }
+
+    #[cfg(feature = "scene")]
+    fn spawn_scene_file(&mut self, path: impl AsRef<std::path::Path>) -> NamedEntities {
+        fixture::spawn_scene_file(self.world_mut(), path.as_ref())
+    }
+
+    #[cfg(feature = "session")]
+    fn save_session(&mut self) {
+        session::save(self.world_mut());
+    }
+
+    #[cfg(feature = "session")]
+    fn replay(&mut self, path: impl AsRef<std::path::Path>) {
+        session::replay(self, path.as_ref());
+    }
+
+    #[cfg(feature = "session")]
+    fn replay_event<
+        E: Event + FromReflect + bevy::reflect::TypePath + bevy::reflect::GetTypeRegistration,
+    >(
+        &mut self,
+    ) {
+        session::replay_event::<E>(self);
+    }
+}
+
+const MAX_DEBUG_LEN: usize = 300;
+
+/// Returns the type id of a system, which identifies it in a schedule, along with its short name.
+fn system_info<I, O, M>(system: impl IntoSystem<I, O, M>) -> (TypeId, String) {
+    let type_id = system.system_type_id();
+    let name = get_short_name(&IntoSystem::into_system(system).name());
+    (type_id, name)
+}
+
+fn mismatch(message: &str, given: impl Debug, found: impl Debug) -> ! {
+    print_failure(message);
+    print_debug("Given:", given);
+    eprintln!();
+    print_debug("Found:", found);
+    panic!("assertion failed");
+}
+
+fn unexpected_match(message: &str, matches: impl Debug) -> ! {
+    print_failure(message);
+    print_debug("Match:", matches);
     panic!("assertion failed");
 }
 
+/// Prints the message of a failed assertion.
+fn print_failure(message: &str) {
+    if is_quiet() {
+        return;
+    }
+    eprintln!("{}", message.red());
+    #[cfg(feature = "harness")]
+    harness::record_detail(message.to_owned());
+    rng::print_seed();
+}
+
+/// Prints a labeled value, on the same line if it fits in one.
+fn print_debug(label: &str, value: impl Debug) {
+    if is_quiet() {
+        return;
+    }
+    let mut value = format!("{:#?}", value);
+    #[cfg(feature = "harness")]
+    harness::record_detail(format!("{label} {}", truncate(&value, " ...")));
+    value = truncate(&value, &" ...".bright_black().to_string());
+    if value.contains('\n') {
+        eprintln!("{}", label.bright_black());
+        eprintln!("{}", value);
+    } else {
+        eprintln!("{} {}", label.bright_black(), value);
+    }
+}
+
+thread_local! {
+    static QUIET: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether failure output is currently silenced on this thread.
+fn is_quiet() -> bool {
+    QUIET.with(Cell::get)
+}
+
+/// Runs `f` without printing failures or panic messages on this thread,
+/// e.g. while proptest reruns a failing test to shrink its input.
+#[cfg(feature = "proptest")]
+fn quietly<R>(f: impl FnOnce() -> R) -> R {
+    struct Reset(bool);
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            QUIET.with(|quiet| quiet.set(self.0));
+        }
+    }
+
+    static HOOK: std::sync::Once = std::sync::Once::new();
+    HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if !is_quiet() {
+                previous(info);
+            }
+        }));
+    });
+
+    let _reset = Reset(QUIET.with(|quiet| quiet.replace(true)));
+    f()
+}
+
+/// Cuts the value after `MAX_DEBUG_LEN` bytes, at a char boundary, and appends `ellipsis`.
+fn truncate(value: &str, ellipsis: &str) -> String {
+    if value.len() <= MAX_DEBUG_LEN {
+        return value.to_owned();
+    }
+    let mut end = MAX_DEBUG_LEN;
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    value[..end].to_owned() + ellipsis
+}
+
+#[doc(hidden)]
+pub mod __private {
+    //! Items used by the code generated by [`bevy_test`](crate::bevy_test).
+
+    use std::panic::{self, AssertUnwindSafe};
+
+    use bevy::prelude::*;
+    use colored::Colorize;
+
+    use crate::{rng, TestApp, TestRng};
+
+    #[cfg(feature = "harness")]
+    pub use crate::harness::BevyTest;
+    #[cfg(feature = "harness")]
+    pub use inventory;
+
+    /// Runs `frames` updates and then the test, printing which test failed if either panics.
+    pub fn run_test(name: &str, frames: u32, mut app: App, test: impl FnOnce(&mut App)) {
+        if !app.world().contains_resource::<TestRng>() {
+            app.insert_resource(TestRng::from_env());
+        }
+        rng::set_seed(app.world().resource::<TestRng>().seed());
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            app.update_n_times(frames);
+            test(&mut app);
+        }));
+        #[cfg(feature = "harness")]
+        if let Some(frame_count) = app.world().get_resource::<bevy::core::FrameCount>() {
+            crate::harness::observe_frame_count(frame_count.0);
+        }
+        if let Err(payload) = result {
+            eprintln!(
+                "{} {}",
+                format!("The test \"{name}\" failed.").red(),
+                format!("(after {frames} initial frames)").bright_black()
+            );
+            // unless an assertion printed it already
+            rng::print_seed();
+            rng::clear_seed();
+            panic::resume_unwind(payload);
+        }
+        rng::clear_seed();
+    }
+}
+
+/// Registers a test generated by [`bevy_test`](crate::bevy_test) with the
+/// [`harness`](crate::harness). Like `#[test]` functions, tests only get registered in `cfg(test)`,
+/// which cargo also sets for `harness = false` targets.
+#[cfg(feature = "harness")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_test {
+    (name: $name:expr, should_panic: $should_panic:expr, expected: $expected:expr, run: $run:expr $(,)?) => {
+        #[cfg(test)]
+        $crate::__private::inventory::submit! {
+            $crate::__private::BevyTest {
+                name: $name,
+                should_panic: $should_panic,
+                expected: $expected,
+                run: $run,
+            }
+        }
+    };
+}
+
+/// Registers a test generated by [`bevy_test`](crate::bevy_test) with the harness,
+/// which is disabled without the `harness` feature.
+#[cfg(not(feature = "harness"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_test {
+    ($($tokens:tt)*) => {};
+}
+
 pub mod p {
-    //! A module that re-exports the entire [`bevy::prelude`] as well as [`TestApp`].
+    //! A module that re-exports the entire [`bevy::prelude`] as well as [`TestApp`],
+    //! [`tree!`](crate::tree) and [`scenario`](crate::scenario()).
 
-    pub use crate::TestApp;
+    pub use crate::{scenario, tree, TestApp};
     pub use bevy::prelude::*;
 }
 
 /// module for doctests
+#[allow(dead_code)]
 mod my_lib {
     use crate::p::*;
 
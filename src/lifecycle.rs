@@ -0,0 +1,326 @@
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use std::{any::type_name, marker::PhantomData};
+
+use crate::{mismatch, unexpected_match};
+
+/// A change in the lifecycle of a component, as recorded by [`App::track_lifecycle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LifecycleEvent {
+    /// The component was added to an entity which didn't have it before.
+    Added,
+    /// The component was inserted, either by adding it or by replacing an existing value.
+    Inserted,
+    /// The component was removed, either explicitly or by despawning the entity.
+    Removed,
+}
+
+/// A resource which records the [`LifecycleEvent`]s of the component `T`.
+/// It gets installed via [`App::track_lifecycle`].
+#[derive(Resource)]
+pub(crate) struct LifecycleLog<T: Component> {
+    pub(crate) events: Vec<(Entity, LifecycleEvent)>,
+    marker: PhantomData<T>,
+}
+
+impl<T: Component> Default for LifecycleLog<T> {
+    fn default() -> Self {
+        Self {
+            events: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+pub(crate) fn record_add<T: Component>(
+    trigger: Trigger<OnAdd, T>,
+    mut log: ResMut<LifecycleLog<T>>,
+) {
+    log.events.push((trigger.entity(), LifecycleEvent::Added));
+}
+
+pub(crate) fn record_insert<T: Component>(
+    trigger: Trigger<OnInsert, T>,
+    mut log: ResMut<LifecycleLog<T>>,
+) {
+    log.events
+        .push((trigger.entity(), LifecycleEvent::Inserted));
+}
+
+pub(crate) fn record_remove<T: Component>(
+    trigger: Trigger<OnRemove, T>,
+    mut log: ResMut<LifecycleLog<T>>,
+) {
+    log.events.push((trigger.entity(), LifecycleEvent::Removed));
+}
+
+/// A struct to perform tests on the lifecycle of a component which is created via [`App::lifecycle`].
+///
+/// ```
+/// use bevy_testing::p::*;
+///
+/// #[derive(Component)]
+/// struct Stunned;
+///
+/// let mut app = App::new();
+/// app.track_lifecycle::<Stunned>();
+///
+/// let enemy = app.spawn(Stunned).id();
+/// app.entity_mut(enemy).remove::<Stunned>();
+///
+/// app.lifecycle::<Stunned>()
+///     .added(enemy)
+///     .removed(enemy)
+///     .inserted_times(enemy, 1);
+/// ```
+pub struct AssertLifecycle<T: Component> {
+    pub(crate) events: Vec<(Entity, LifecycleEvent)>,
+    pub(crate) invert: bool,
+    pub(crate) marker: PhantomData<T>,
+}
+
+impl<T: Component> AssertLifecycle<T> {
+    /// Returns an inverted [`AssertLifecycle`].
+    /// When chaining methods,
+    /// the inverted state gets reset after every method.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Stunned;
+    ///
+    /// let mut app = App::new();
+    /// app.track_lifecycle::<Stunned>();
+    /// let enemy = app.spawn(Stunned).id();
+    ///
+    /// app.lifecycle::<Stunned>()
+    ///     .not().removed(enemy);
+    /// ```
+    #[allow(clippy::should_implement_trait)] // users should not need to import std::ops::Not
+    pub fn not(mut self) -> Self {
+        self.invert = !self.invert;
+        self
+    }
+
+    /// Checks if the component was added to the given entity.
+    ///
+    /// This can be inverted via [`Self::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Stunned;
+    ///
+    /// let mut app = App::new();
+    /// app.track_lifecycle::<Stunned>();
+    /// let enemy = app.spawn_empty().id();
+    /// let player = app.spawn_empty().id();
+    /// app.entity_mut(enemy).insert(Stunned);
+    ///
+    /// app.lifecycle::<Stunned>()
+    ///     .added(enemy)
+    ///     .not().added(player);
+    /// ```
+    pub fn added(self, entity: Entity) -> Self {
+        self.check(entity, LifecycleEvent::Added, "added to")
+    }
+
+    /// Checks if the component was removed from the given entity.
+    /// This includes removals caused by despawning the entity.
+    ///
+    /// This can be inverted via [`Self::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Stunned;
+    ///
+    /// let mut app = App::new();
+    /// app.track_lifecycle::<Stunned>();
+    /// let enemy = app.spawn(Stunned).id();
+    /// let player = app.spawn(Stunned).id();
+    /// app.world_mut().despawn(enemy);
+    ///
+    /// app.lifecycle::<Stunned>()
+    ///     .removed(enemy)
+    ///     .not().removed(player);
+    /// ```
+    pub fn removed(self, entity: Entity) -> Self {
+        self.check(entity, LifecycleEvent::Removed, "removed from")
+    }
+
+    /// Checks if the component was inserted into the given entity while it already had the component,
+    /// overwriting the previous value.
+    ///
+    /// This can be inverted via [`Self::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.track_lifecycle::<Health>();
+    /// let enemy = app.spawn(Health(10)).id();
+    /// let player = app.spawn(Health(10)).id();
+    /// app.entity_mut(enemy).insert(Health(5));
+    ///
+    /// app.lifecycle::<Health>()
+    ///     .replaced(enemy)
+    ///     .not().replaced(player);
+    /// ```
+    pub fn replaced(self, entity: Entity) -> Self {
+        if self.invert {
+            return self.not_replaced(entity);
+        }
+
+        if self.replacements(entity) == 0 {
+            mismatch(
+                &format!(
+                    "The component \"{}\" was never replaced on the entity.",
+                    type_name::<T>()
+                ),
+                entity,
+                self.history(entity),
+            );
+        }
+
+        self
+    }
+    fn not_replaced(self, entity: Entity) -> Self {
+        if self.replacements(entity) != 0 {
+            unexpected_match(
+                &format!(
+                    "The component \"{}\" was replaced on the entity.",
+                    type_name::<T>()
+                ),
+                self.history(entity),
+            );
+        }
+
+        self.reset_invert()
+    }
+
+    /// Checks if the component was inserted into the given entity the given amount of times.
+    /// Both adding the component and replacing its value count as an insertion.
+    ///
+    /// This can be inverted via [`Self::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.track_lifecycle::<Health>();
+    /// let enemy = app.spawn(Health(10)).id();
+    /// app.entity_mut(enemy).insert(Health(5));
+    ///
+    /// app.lifecycle::<Health>()
+    ///     .inserted_times(enemy, 2)
+    ///     .not().inserted_times(enemy, 1);
+    /// ```
+    pub fn inserted_times(self, entity: Entity, given: usize) -> Self {
+        if self.invert {
+            return self.not_inserted_times(entity, given);
+        }
+
+        let found = self.count(entity, LifecycleEvent::Inserted);
+        if found != given {
+            mismatch(
+                &format!(
+                    "The amount of insertions of \"{}\" into the entity mismatches.",
+                    type_name::<T>()
+                ),
+                given,
+                found,
+            );
+        }
+
+        self
+    }
+    fn not_inserted_times(self, entity: Entity, given: usize) -> Self {
+        if self.count(entity, LifecycleEvent::Inserted) == given {
+            unexpected_match(
+                &format!(
+                    "The amount of insertions of \"{}\" into the entity matches.",
+                    type_name::<T>()
+                ),
+                given,
+            );
+        }
+
+        self.reset_invert()
+    }
+
+    fn check(self, entity: Entity, event: LifecycleEvent, action: &str) -> Self {
+        if self.invert {
+            return self.not_check(entity, event, action);
+        }
+
+        if self.count(entity, event) == 0 {
+            mismatch(
+                &format!(
+                    "The component \"{}\" was never {action} the entity.",
+                    type_name::<T>()
+                ),
+                entity,
+                self.history(entity),
+            );
+        }
+
+        self
+    }
+    fn not_check(self, entity: Entity, event: LifecycleEvent, action: &str) -> Self {
+        if self.count(entity, event) != 0 {
+            unexpected_match(
+                &format!(
+                    "The component \"{}\" was {action} the entity.",
+                    type_name::<T>()
+                ),
+                self.history(entity),
+            );
+        }
+
+        self.reset_invert()
+    }
+
+    fn count(&self, entity: Entity, event: LifecycleEvent) -> usize {
+        self.events
+            .iter()
+            .filter(|(e, ev)| *e == entity && *ev == event)
+            .count()
+    }
+
+    /// An insertion which doesn't directly follow an addition overwrote an existing value.
+    fn replacements(&self, entity: Entity) -> usize {
+        let history = self.history(entity);
+        history
+            .iter()
+            .enumerate()
+            .filter(|(i, event)| {
+                **event == LifecycleEvent::Inserted
+                    && (*i == 0 || history[i - 1] != LifecycleEvent::Added)
+            })
+            .count()
+    }
+
+    fn history(&self, entity: Entity) -> Vec<LifecycleEvent> {
+        self.events
+            .iter()
+            .filter(|(e, _)| *e == entity)
+            .map(|(_, event)| *event)
+            .collect()
+    }
+
+    fn reset_invert(mut self) -> Self {
+        self.invert = false;
+        self
+    }
+}
@@ -0,0 +1,188 @@
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use std::{any::type_name, marker::PhantomData};
+
+use crate::{mismatch, unexpected_match};
+
+/// A resource which records the target of every trigger of the event `E`.
+/// It gets installed via [`App::spy_triggers`].
+#[derive(Resource)]
+pub(crate) struct TriggerSpy<E: Event> {
+    pub(crate) targets: Vec<Option<Entity>>,
+    marker: PhantomData<E>,
+}
+
+impl<E: Event> Default for TriggerSpy<E> {
+    fn default() -> Self {
+        Self {
+            targets: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+pub(crate) fn record_trigger<E: Event>(trigger: Trigger<E>, mut spy: ResMut<TriggerSpy<E>>) {
+    let target = trigger.entity();
+    spy.targets
+        .push((target != Entity::PLACEHOLDER).then_some(target));
+}
+
+/// A struct to perform tests on the triggers of an event which is created via [`App::triggers`].
+///
+/// ```
+/// use bevy_testing::p::*;
+///
+/// #[derive(Event)]
+/// struct Damage(u32);
+///
+/// let mut app = App::new();
+/// app.spy_triggers::<Damage>();
+///
+/// let enemy = app.spawn_empty().id();
+/// app.trigger_targets(Damage(5), enemy);
+/// app.trigger_targets(Damage(3), enemy);
+/// app.trigger(Damage(1));
+///
+/// app.triggers::<Damage>()
+///     .count(3)
+///     .targeted(enemy)
+///     .count(2);
+/// ```
+pub struct AssertTriggers<E: Event> {
+    pub(crate) targets: Vec<Option<Entity>>,
+    pub(crate) invert: bool,
+    pub(crate) marker: PhantomData<E>,
+}
+
+impl<E: Event> AssertTriggers<E> {
+    /// Returns an inverted [`AssertTriggers`].
+    /// When chaining methods,
+    /// the inverted state gets reset after every method.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Event)]
+    /// struct Damage(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.spy_triggers::<Damage>();
+    /// app.trigger(Damage(5));
+    ///
+    /// app.triggers::<Damage>()
+    ///     .not().count(2);
+    /// ```
+    #[allow(clippy::should_implement_trait)] // users should not need to import std::ops::Not
+    pub fn not(mut self) -> Self {
+        self.invert = !self.invert;
+        self
+    }
+
+    /// Only keeps the triggers which targeted the given entity.
+    /// This does not perform a test on its own and doesn't reset the inverted state.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Event)]
+    /// struct Damage(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.spy_triggers::<Damage>();
+    ///
+    /// let player = app.spawn_empty().id();
+    /// let enemy = app.spawn_empty().id();
+    /// app.trigger_targets(Damage(5), vec![player, enemy]);
+    /// app.trigger_targets(Damage(3), enemy);
+    ///
+    /// app.triggers::<Damage>()
+    ///     .targeted(enemy)
+    ///     .count(2);
+    /// ```
+    pub fn targeted(mut self, entity: Entity) -> Self {
+        self.targets.retain(|target| *target == Some(entity));
+        self
+    }
+
+    /// Only keeps the triggers which didn't target any entity.
+    /// This does not perform a test on its own and doesn't reset the inverted state.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Event)]
+    /// struct Damage(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.spy_triggers::<Damage>();
+    ///
+    /// let enemy = app.spawn_empty().id();
+    /// app.trigger_targets(Damage(5), enemy);
+    /// app.trigger(Damage(3));
+    ///
+    /// app.triggers::<Damage>()
+    ///     .untargeted()
+    ///     .count(1);
+    /// ```
+    pub fn untargeted(mut self) -> Self {
+        self.targets.retain(Option::is_none);
+        self
+    }
+
+    /// Checks if the event was triggered the given amount of times.
+    ///
+    /// This can be inverted via [`Self::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Event)]
+    /// struct Damage(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.spy_triggers::<Damage>();
+    /// app.trigger(Damage(5));
+    /// app.trigger(Damage(3));
+    ///
+    /// app.triggers::<Damage>()
+    ///     .count(2)
+    ///     .not().count(0);
+    /// ```
+    pub fn count(self, given: usize) -> Self {
+        if self.invert {
+            return self.not_count(given);
+        }
+
+        if self.targets.len() != given {
+            mismatch(
+                &format!(
+                    "The amount of triggers of \"{}\" mismatches.",
+                    type_name::<E>()
+                ),
+                given,
+                self.targets.len(),
+            );
+        }
+
+        self
+    }
+    fn not_count(self, given: usize) -> Self {
+        if self.targets.len() == given {
+            unexpected_match(
+                &format!(
+                    "The amount of triggers of \"{}\" matches.",
+                    type_name::<E>()
+                ),
+                given,
+            );
+        }
+
+        self.reset_invert()
+    }
+
+    fn reset_invert(mut self) -> Self {
+        self.invert = false;
+        self
+    }
+}
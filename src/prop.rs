@@ -0,0 +1,241 @@
+//! Helpers for property based testing with [proptest](https://docs.rs/proptest).
+//!
+//! This module requires the `proptest` feature.
+//!
+//! ```
+//! use bevy_testing::p::*;
+//! use bevy_testing::prop::{arb_world, check};
+//! use proptest::prelude::*;
+//!
+//! #[derive(Component, Clone, Debug)]
+//! struct Gold(u32);
+//!
+//! fn trade(mut query: Query<&mut Gold>) {
+//!     let mut golds = query.iter_mut().collect::<Vec<_>>();
+//!     if let [a, b, ..] = &mut golds[..] {
+//!         let amount = a.0 / 2;
+//!         a.0 -= amount;
+//!         b.0 += amount;
+//!     }
+//! }
+//!
+//! check(
+//!     arb_world((0..1000u32).prop_map(Gold), 0..10),
+//!     || {
+//!         let mut app = App::new();
+//!         app.add_systems(Update, trade);
+//!         app
+//!     },
+//!     |app, world| {
+//!         world.spawn(app);
+//!         app.update_once();
+//!
+//!         // trading conserves gold
+//!         let before = world.entities.iter().map(|gold| gold.0).sum::<u32>();
+//!         let mut query = app.world_mut().query::<&Gold>();
+//!         let after = query.iter(app.world()).map(|gold| gold.0).sum::<u32>();
+//!         assert_eq!(before, after);
+//!     },
+//! );
+//! ```
+
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use std::{fmt::Debug, hash::Hash};
+
+use colored::Colorize;
+use proptest::{
+    collection::{vec, SizeRange},
+    strategy::Strategy,
+    test_runner::{Config, TestError, TestRunner},
+};
+
+use crate::{print_debug, print_failure, quietly};
+
+/// A list of entities to spawn, each described by a [`Bundle`].
+/// Created via [`arb_world`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldSetup<B> {
+    /// The bundles of the entities, in spawn order.
+    pub entities: Vec<B>,
+}
+
+impl<B: Bundle + Clone> WorldSetup<B> {
+    /// Spawns all entities, returning their ids in the same order.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    /// use bevy_testing::prop::WorldSetup;
+    ///
+    /// #[derive(Component, Clone, Debug)]
+    /// struct Gold(u32);
+    ///
+    /// let mut app = App::new();
+    /// let setup = WorldSetup { entities: vec![Gold(1), Gold(2)] };
+    /// let entities = setup.spawn(&mut app);
+    ///
+    /// assert_eq!(app.component::<Gold>(entities[1]).0, 2);
+    /// ```
+    pub fn spawn(&self, app: &mut App) -> Vec<Entity> {
+        self.entities
+            .iter()
+            .map(|bundle| app.world_mut().spawn(bundle.clone()).id())
+            .collect()
+    }
+}
+
+/// A strategy for a [`WorldSetup`] with `size` entities, each having a bundle generated by `bundle`.
+/// A tuple of component strategies generates a tuple of components, which is a bundle itself.
+///
+/// Shrinking removes entities and simplifies their components.
+pub fn arb_world<S>(
+    bundle: S,
+    size: impl Into<SizeRange>,
+) -> impl Strategy<Value = WorldSetup<S::Value>>
+where
+    S: Strategy,
+    S::Value: Bundle + Clone,
+{
+    vec(bundle, size).prop_map(|entities| WorldSetup { entities })
+}
+
+/// A sequence of frames, each listing the buttons which are held down during that frame.
+/// Created via [`arb_inputs`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputScript<K> {
+    /// The held buttons per frame.
+    pub frames: Vec<Vec<K>>,
+}
+
+impl<K: Copy + Eq + Hash + Send + Sync + 'static> InputScript<K> {
+    /// Updates the app once per frame, pressing and releasing the buttons in the [`ButtonInput`]
+    /// resource accordingly. The resource gets inserted if it doesn't exist.
+    ///
+    /// The `InputPlugin` clears the resource at the start of every update,
+    /// so `just_pressed` and `just_released` only work without it.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    /// use bevy_testing::prop::InputScript;
+    ///
+    /// #[derive(Resource, Default)]
+    /// struct Jumps(u32);
+    ///
+    /// fn jump(input: Res<ButtonInput<KeyCode>>, mut jumps: ResMut<Jumps>) {
+    ///     if input.just_pressed(KeyCode::Space) {
+    ///         jumps.0 += 1;
+    ///     }
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.init_resource::<Jumps>();
+    /// app.add_systems(Update, jump);
+    ///
+    /// let space = KeyCode::Space;
+    /// InputScript { frames: vec![vec![space], vec![space], vec![], vec![space]] }.run(&mut app);
+    /// assert_eq!(app.world().resource::<Jumps>().0, 2);
+    /// ```
+    pub fn run(&self, app: &mut App) {
+        app.world_mut()
+            .get_resource_or_insert_with(ButtonInput::<K>::default);
+        for held in &self.frames {
+            let mut input = app.world_mut().resource_mut::<ButtonInput<K>>();
+            let released = input
+                .get_pressed()
+                .filter(|button| !held.contains(button))
+                .copied()
+                .collect::<Vec<_>>();
+            for button in released {
+                input.release(button);
+            }
+            for button in held {
+                input.press(*button);
+            }
+
+            app.update_once();
+            app.world_mut().resource_mut::<ButtonInput<K>>().clear();
+        }
+    }
+}
+
+/// A strategy for an [`InputScript`] with `frames` frames, during each of which up to three
+/// buttons generated by `button` are held down.
+///
+/// Shrinking removes frames and held buttons.
+pub fn arb_inputs<S>(
+    button: S,
+    frames: impl Into<SizeRange>,
+) -> impl Strategy<Value = InputScript<S::Value>>
+where
+    S: Strategy,
+    S::Value: Copy + Eq + Hash + Send + Sync + 'static,
+{
+    vec(vec(button, 0..=3), frames).prop_map(|frames| InputScript { frames })
+}
+
+/// Runs `test` with many values generated by `strategy`, building a fresh app via `build` for each.
+/// The amount of cases can be set via the `PROPTEST_CASES` environment variable.
+///
+/// If a case fails, the value is shrunk to a minimal failing one, which is printed before panicking.
+/// The failures while shrinking are silenced, only the minimal one is run again with its output.
+pub fn check<S>(strategy: S, build: impl Fn() -> App, test: impl Fn(&mut App, &S::Value))
+where
+    S: Strategy,
+    S::Value: Debug,
+{
+    check_with(Config::default(), strategy, build, test);
+}
+
+/// Like [`check`], but with a custom proptest [`Config`].
+///
+/// ```
+/// use bevy_testing::p::*;
+/// use bevy_testing::prop::check_with;
+/// use proptest::test_runner::Config;
+///
+/// check_with(
+///     Config::with_cases(16),
+///     0..100u32,
+///     App::new,
+///     |app, frames| {
+///         app.update_n_times(*frames);
+///     },
+/// );
+/// ```
+pub fn check_with<S>(
+    mut config: Config,
+    strategy: S,
+    build: impl Fn() -> App,
+    test: impl Fn(&mut App, &S::Value),
+) where
+    S: Strategy,
+    S::Value: Debug,
+{
+    // there is no source file to store regressions next to
+    config.failure_persistence = None;
+    let mut runner = TestRunner::new(config);
+    let result = quietly(|| {
+        runner.run(&strategy, |value| {
+            let mut app = build();
+            test(&mut app, &value);
+            Ok(())
+        })
+    });
+
+    match result {
+        Ok(()) => {}
+        Err(TestError::Fail(reason, value)) => {
+            eprintln!();
+            print_failure("The property doesn't hold.");
+            print_debug("Minimal input:", &value);
+            eprintln!("{} {reason}", "Reason:".bright_black());
+            eprintln!("{}", "Rerunning the minimal input:".bright_black());
+            let mut app = build();
+            test(&mut app, &value);
+            eprintln!("{}", "The minimal input passed when rerun.".bright_black());
+            panic!("assertion failed");
+        }
+        Err(TestError::Abort(reason)) => panic!("the property test was aborted: {reason}"),
+    }
+}
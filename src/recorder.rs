@@ -0,0 +1,233 @@
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use std::fmt::Debug;
+
+use colored::Colorize;
+
+use crate::{print_debug, print_failure};
+
+/// A struct to perform temporal tests on values sampled once per frame, which is created via
+/// [`App::record`] or [`App::record_resource`].
+///
+/// Frames are counted from `1`, frame `n` being the value after the `n`th update.
+///
+/// ```
+/// use bevy_testing::p::*;
+///
+/// #[derive(Component)]
+/// struct Health(i32);
+///
+/// fn poison(mut query: Query<&mut Health>) {
+///     for mut health in &mut query {
+///         health.0 = (health.0 - 3).max(0);
+///     }
+/// }
+///
+/// let mut app = App::new();
+/// app.add_systems(Update, poison);
+/// let player = app.spawn(Health(10)).id();
+///
+/// app.record(10, |world| world.get::<Health>(player).unwrap().0)
+///     .always(|health| *health >= 0)
+///     .eventually_within(4, |health| *health == 0)
+///     .monotonic_by_key(|health| -health);
+/// ```
+pub struct Recorder<V> {
+    pub(crate) frames: Vec<V>,
+}
+
+impl<V: Debug> Recorder<V> {
+    /// Returns the recorded values, the value of frame `n` being at index `n - 1`.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// let mut app = App::new();
+    /// let recorder = app.record(3, |world| world.change_tick());
+    ///
+    /// assert_eq!(recorder.values().len(), 3);
+    /// ```
+    pub fn values(&self) -> &[V] {
+        &self.frames
+    }
+
+    /// Checks if the predicate holds on every frame.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Resource, Clone, Debug)]
+    /// struct Score(u32);
+    ///
+    /// fn score(mut score: ResMut<Score>) {
+    ///     score.0 += 10;
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.insert_resource(Score(0));
+    /// app.add_systems(Update, score);
+    ///
+    /// app.record_resource::<Score>(5)
+    ///     .always(|score| score.0 % 10 == 0);
+    /// ```
+    pub fn always(self, predicate: impl Fn(&V) -> bool) -> Self {
+        if let Some(frame) = self.frames.iter().position(|value| !predicate(value)) {
+            self.fail("The property doesn't hold on every frame.", &[frame]);
+        }
+
+        self
+    }
+
+    /// Checks if the predicate holds on no frame.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Resource, Clone, Debug)]
+    /// struct Score(u32);
+    ///
+    /// fn score(mut score: ResMut<Score>) {
+    ///     score.0 += 10;
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.insert_resource(Score(0));
+    /// app.add_systems(Update, score);
+    ///
+    /// app.record_resource::<Score>(5)
+    ///     .never(|score| score.0 > 50);
+    /// ```
+    pub fn never(self, predicate: impl Fn(&V) -> bool) -> Self {
+        if let Some(frame) = self.frames.iter().position(predicate) {
+            self.fail("The property holds on a frame.", &[frame]);
+        }
+
+        self
+    }
+
+    /// Checks if the predicate holds on at least one of the first `frames` frames.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Resource, Clone, Debug)]
+    /// struct Door {
+    ///     timer: u32,
+    ///     open: bool,
+    /// }
+    ///
+    /// fn open(mut door: ResMut<Door>) {
+    ///     door.timer += 1;
+    ///     door.open = door.timer >= 3;
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.insert_resource(Door { timer: 0, open: false });
+    /// app.add_systems(Update, open);
+    ///
+    /// app.record_resource::<Door>(10)
+    ///     .eventually_within(3, |door| door.open);
+    /// ```
+    pub fn eventually_within(self, frames: usize, predicate: impl Fn(&V) -> bool) -> Self {
+        let window = &self.frames[..frames.min(self.frames.len())];
+        if !window.iter().any(predicate) {
+            let message = format!("The property doesn't hold within {frames} frames.");
+            match window.len() {
+                0 => self.fail_empty(&message),
+                len => self.fail(&message, &[len - 1]),
+            }
+        }
+
+        self
+    }
+
+    /// Checks if `hold` holds on every frame until `release` holds for the first time,
+    /// and that `release` holds eventually.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Resource, Clone, Debug)]
+    /// struct Door {
+    ///     timer: u32,
+    ///     open: bool,
+    /// }
+    ///
+    /// fn open(mut door: ResMut<Door>) {
+    ///     door.timer += 1;
+    ///     door.open = door.timer >= 3;
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.insert_resource(Door { timer: 0, open: false });
+    /// app.add_systems(Update, open);
+    ///
+    /// app.record_resource::<Door>(10)
+    ///     .until(|door| !door.open, |door| door.timer == 3);
+    /// ```
+    pub fn until(self, hold: impl Fn(&V) -> bool, release: impl Fn(&V) -> bool) -> Self {
+        for (frame, value) in self.frames.iter().enumerate() {
+            if release(value) {
+                return self;
+            }
+            if !hold(value) {
+                self.fail(
+                    "The property stopped holding before the release condition held.",
+                    &[frame],
+                );
+            }
+        }
+
+        match self.frames.len() {
+            0 => self.fail_empty("The release condition never held."),
+            len => self.fail("The release condition never held.", &[len - 1]),
+        }
+    }
+
+    /// Checks if the key of the values never decreases from one frame to the next.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Resource, Clone, Debug)]
+    /// struct Score(u32);
+    ///
+    /// fn score(mut score: ResMut<Score>) {
+    ///     score.0 += 10;
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.insert_resource(Score(0));
+    /// app.add_systems(Update, score);
+    ///
+    /// app.record_resource::<Score>(5)
+    ///     .monotonic_by_key(|score| score.0);
+    /// ```
+    pub fn monotonic_by_key<K: PartialOrd>(self, key: impl Fn(&V) -> K) -> Self {
+        let decrease = self
+            .frames
+            .windows(2)
+            .position(|pair| key(&pair[1]) < key(&pair[0]));
+        if let Some(frame) = decrease {
+            self.fail("The key decreased between two frames.", &[frame, frame + 1]);
+        }
+
+        self
+    }
+
+    /// Prints the values at the given (zero-based) frames.
+    fn fail(&self, message: &str, frames: &[usize]) -> ! {
+        print_failure(message);
+        for frame in frames {
+            print_debug(&format!("Frame {}:", frame + 1), &self.frames[*frame]);
+        }
+        panic!("assertion failed");
+    }
+
+    fn fail_empty(&self, message: &str) -> ! {
+        print_failure(message);
+        eprintln!("{}", "No frames were recorded.".bright_black());
+        panic!("assertion failed");
+    }
+}
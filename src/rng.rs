@@ -0,0 +1,191 @@
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use std::{
+    cell::Cell,
+    collections::hash_map::RandomState,
+    env,
+    hash::{BuildHasher, Hasher},
+};
+
+use colored::Colorize;
+
+/// The environment variable which overrides the seed of every [`TestRng`] installed by a test.
+pub(crate) const SEED_VAR: &str = "BEVY_TESTING_SEED";
+
+thread_local! {
+    /// The seed of the test running on this thread, and whether it was printed already.
+    static SEED: Cell<Option<(u64, bool)>> = const { Cell::new(None) };
+}
+
+/// A small, seedable random number generator for systems which need randomness in tests.
+///
+/// Every [`bevy_test`](crate::bevy_test) installs one with a random seed. To pin it, use
+/// [`App::seed`]. Either way, the seed gets printed if the test fails, and to rerun it with the
+/// printed seed, set the `BEVY_TESTING_SEED` environment variable, which overrides both.
+///
+/// With the `rand` feature, it implements `RngCore` and `SeedableRng`, so it works with the
+/// distributions of `rand`.
+///
+/// ```
+/// use bevy_testing::p::*;
+/// use bevy_testing::TestRng;
+///
+/// #[derive(Component)]
+/// struct Enemy;
+///
+/// fn spawn_enemies(mut commands: Commands, mut rng: ResMut<TestRng>) {
+///     for _ in 0..rng.below(5) {
+///         commands.spawn(Enemy);
+///     }
+/// }
+///
+/// let mut app = App::new();
+/// app.add_systems(Update, spawn_enemies);
+/// app.seed(42);
+/// app.update_once();
+///
+/// let enemies = app.world_mut().query::<&Enemy>().iter(app.world()).count();
+/// assert!(enemies < 5);
+/// ```
+#[derive(Resource, Debug, Clone)]
+pub struct TestRng {
+    seed: u64,
+    state: u64,
+}
+
+impl TestRng {
+    /// Creates a generator with the given seed. The same seed always leads to the same numbers.
+    ///
+    /// ```
+    /// use bevy_testing::TestRng;
+    ///
+    /// let mut a = TestRng::new(7);
+    /// let mut b = TestRng::new(7);
+    /// assert_eq!(a.next_u64(), b.next_u64());
+    /// ```
+    pub fn new(seed: u64) -> Self {
+        Self { seed, state: seed }
+    }
+
+    /// Creates a generator with the seed from `BEVY_TESTING_SEED`, or a random one if it isn't set.
+    ///
+    /// Panics if the variable isn't a number.
+    pub fn from_env() -> Self {
+        Self::new(env_seed().unwrap_or_else(|| RandomState::new().build_hasher().finish()))
+    }
+
+    /// Returns the seed the generator was created with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns a random `u64`.
+    pub fn next_u64(&mut self) -> u64 {
+        // splitmix64
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a random `u32`.
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Returns a random `f32` in `0.0..1.0`.
+    ///
+    /// ```
+    /// use bevy_testing::TestRng;
+    ///
+    /// let mut rng = TestRng::new(1);
+    /// let value = rng.next_f32();
+    /// assert!((0.0..1.0).contains(&value));
+    /// ```
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Returns a random number in `0..n`.
+    ///
+    /// Panics if `n` is 0.
+    ///
+    /// ```
+    /// use bevy_testing::TestRng;
+    ///
+    /// let mut rng = TestRng::new(1);
+    /// assert!(rng.below(6) < 6);
+    /// ```
+    pub fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0, "the upper bound must be greater than 0");
+        self.next_u64() % n
+    }
+}
+
+#[cfg(feature = "rand")]
+impl rand_core::RngCore for TestRng {
+    fn next_u32(&mut self) -> u32 {
+        TestRng::next_u32(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        TestRng::next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rand_core::impls::fill_bytes_via_next(self, dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rand")]
+impl rand_core::SeedableRng for TestRng {
+    type Seed = [u8; 8];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self::new(u64::from_le_bytes(seed))
+    }
+
+    fn seed_from_u64(seed: u64) -> Self {
+        Self::new(seed)
+    }
+}
+
+/// Returns the seed set via `BEVY_TESTING_SEED`.
+pub(crate) fn env_seed() -> Option<u64> {
+    let value = env::var(SEED_VAR).ok()?;
+    Some(
+        value
+            .trim()
+            .parse()
+            .unwrap_or_else(|_| panic!("{SEED_VAR} must be a number, but it is \"{value}\"")),
+    )
+}
+
+/// Remembers the seed of the test running on this thread, to print it if the test fails.
+pub(crate) fn set_seed(seed: u64) {
+    SEED.set(Some((seed, false)));
+}
+
+/// Forgets the seed once the test is done, as the thread may run another test.
+pub(crate) fn clear_seed() {
+    SEED.set(None);
+}
+
+/// Prints the seed of the test running on this thread, unless it was printed already.
+pub(crate) fn print_seed() {
+    let Some((seed, false)) = SEED.get() else {
+        return;
+    };
+    SEED.set(Some((seed, true)));
+    let line = format!("Seed: {seed}, rerun it via {SEED_VAR}={seed}");
+    #[cfg(feature = "harness")]
+    crate::harness::record_detail(line.clone());
+    eprintln!("{}", line.bright_black());
+}
@@ -0,0 +1,799 @@
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+#[cfg(feature = "harness")]
+use std::sync::Arc;
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+
+use bevy::reflect::{
+    serde::TypedReflectDeserializer, DynamicEnum, DynamicVariant, TypeInfo, TypeRegistration,
+    TypeRegistry, Typed,
+};
+use colored::Colorize;
+use serde::de::{
+    self, Deserialize, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor,
+};
+
+#[cfg(feature = "harness")]
+use crate::harness::{self, Trial};
+use crate::{
+    fixture::NamedEntities, mismatch, print_debug, print_failure, scenario::panic_message,
+};
+
+type AddPlugin = Box<dyn Fn(&mut App) + Send + Sync>;
+
+/// An entity to spawn, with its name and components.
+type EntityDef = (String, Vec<(ComponentType, Box<dyn Reflect>)>);
+
+/// Runs scenario files, which describe a test in [RON](https://docs.rs/ron) instead of Rust.
+///
+/// This requires the `runner` feature.
+///
+/// A scenario file lists the plugins to add, the entities to spawn and the steps to run:
+///
+/// ```ron
+/// (
+///     plugins: ["fall_damage"],
+///     spawn: [
+///         (name: "player", components: {
+///             "Health": (100),
+///             "Falling": (),
+///         }),
+///     ],
+///     steps: [
+///         Press("Space"),
+///         Update(5),
+///         Release("Space"),
+///         Expect(entity: "player", component: "Health", value: (90)),
+///         Missing(entity: "player", component: "Falling"),
+///     ],
+/// )
+/// ```
+///
+/// The plugins are looked up by the names they were registered with via
+/// [`ScenarioRunner::plugin`], so `plugins` needs to be the first field.
+/// Components are given by their type path or, if it is unique, their short type path.
+/// They need to be registered by one of the plugins and reflect [`Component`].
+///
+/// step                                     | description
+/// -----------------------------------------|--
+/// `Update(n)`                              | updates the app `n` times
+/// `Press("Space")`                         | presses a [`KeyCode`] in the [`ButtonInput`] resource
+/// `Release("Space")`                       | releases a [`KeyCode`]
+/// `Expect(entity, component, value)`       | checks if the component of the entity equals `value`
+/// `Has(entity, component)`                 | checks if the entity has the component
+/// `Missing(entity, component)`             | checks if the entity doesn't have the component
+///
+/// The input is cleared after every update, so `just_pressed` and `just_released` hold for one
+/// frame. This only works without the `InputPlugin`, which clears it at the start of every update.
+///
+/// The plugins need to be compiled with the game, so there is no prebuilt binary. Instead, add a
+/// test which registers the plugins and runs a directory of scenario files. With the `harness`
+/// feature, every file can be its own test via [`ScenarioRunner::trials`].
+///
+/// ```no_run
+/// use bevy_testing::p::*;
+/// use bevy_testing::ScenarioRunner;
+///
+/// # struct GamePlugin;
+/// # impl Plugin for GamePlugin {
+/// #     fn build(&self, _: &mut App) {}
+/// # }
+/// #[test]
+/// fn scenarios() {
+///     ScenarioRunner::new()
+///         .plugin("game", |app| {
+///             app.add_plugins(GamePlugin);
+///         })
+///         .run_dir("tests/scenarios");
+/// }
+/// ```
+pub struct ScenarioRunner {
+    plugins: BTreeMap<String, AddPlugin>,
+}
+
+impl Default for ScenarioRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScenarioRunner {
+    /// Creates a runner without any plugins.
+    pub fn new() -> Self {
+        Self {
+            plugins: BTreeMap::new(),
+        }
+    }
+
+    /// Registers a function which scenario files can refer to by `name` in their `plugins`.
+    /// It usually adds a plugin, but may set up the app in any other way too.
+    pub fn plugin(
+        mut self,
+        name: impl Into<String>,
+        add: impl Fn(&mut App) + Send + Sync + 'static,
+    ) -> Self {
+        self.plugins.insert(name.into(), Box::new(add));
+        self
+    }
+
+    /// Runs the given scenario, using `name` to label failures.
+    ///
+    /// Panics if the scenario can't be parsed or one of its steps fails.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    /// use bevy_testing::ScenarioRunner;
+    ///
+    /// #[derive(Component, Reflect, Debug)]
+    /// #[reflect(Component)]
+    /// struct Health(u32);
+    ///
+    /// #[derive(Component, Reflect)]
+    /// #[reflect(Component)]
+    /// struct Falling;
+    ///
+    /// fn fall_damage(mut commands: Commands, mut query: Query<(Entity, &mut Health), With<Falling>>) {
+    ///     for (entity, mut health) in &mut query {
+    ///         health.0 -= 10;
+    ///         commands.entity(entity).remove::<Falling>();
+    ///     }
+    /// }
+    ///
+    /// ScenarioRunner::new()
+    ///     .plugin("fall_damage", |app| {
+    ///         app.register_type::<Health>();
+    ///         app.register_type::<Falling>();
+    ///         app.add_systems(Update, fall_damage);
+    ///     })
+    ///     .run_source("fall damage", r#"(
+    ///         plugins: ["fall_damage"],
+    ///         spawn: [
+    ///             (name: "player", components: { "Health": (100), "Falling": () }),
+    ///         ],
+    ///         steps: [
+    ///             Has(entity: "player", component: "Falling"),
+    ///             Update(5),
+    ///             Expect(entity: "player", component: "Health", value: (90)),
+    ///             Missing(entity: "player", component: "Falling"),
+    ///         ],
+    ///     )"#);
+    /// ```
+    pub fn run_source(&self, name: &str, source: &str) {
+        let mut deserializer = ron::de::Deserializer::from_str(source)
+            .unwrap_or_else(|err| panic!("failed to parse scenario \"{name}\": {err}"));
+        let file = ScenarioSeed { runner: self }
+            .deserialize(&mut deserializer)
+            .and_then(|file| deserializer.end().map(|_| file))
+            .unwrap_or_else(|err| {
+                panic!(
+                    "failed to parse scenario \"{name}\": {}",
+                    deserializer.span_error(err)
+                )
+            });
+        file.run(name);
+    }
+
+    /// Runs the scenario file at `path`.
+    ///
+    /// Panics if the file can't be read or parsed, or one of its steps fails.
+    pub fn run_file(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .unwrap_or_else(|err| panic!("failed to read scenario file {}: {err}", path.display()));
+        self.run_source(&path.display().to_string(), &source);
+    }
+
+    /// Runs every `*.scenario.ron` file in the directory and its subdirectories, sorted by path.
+    ///
+    /// All files run even if some of them fail. Afterwards, every failed file is reported,
+    /// and the runner panics. It also panics if the directory doesn't contain any scenario file.
+    pub fn run_dir(&self, dir: impl AsRef<Path>) {
+        let files = scenario_files(dir.as_ref());
+
+        let mut failures = Vec::new();
+        for path in &files {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| self.run_file(path)));
+            if let Err(payload) = result {
+                failures.push((path, panic_message(payload)));
+            }
+        }
+
+        if failures.is_empty() {
+            return;
+        }
+        eprintln!();
+        eprintln!(
+            "{}",
+            format!(
+                "{} of {} scenario files failed.",
+                failures.len(),
+                files.len()
+            )
+            .red()
+        );
+        for (path, message) in failures {
+            eprintln!();
+            eprintln!("{}", format!("{}:", path.display()).bright_black());
+            eprintln!("{} {message}", "Panic:".bright_black());
+        }
+        panic!("assertion failed");
+    }
+
+    /// Returns one test for every `*.scenario.ron` file in the directory and its subdirectories,
+    /// named after its path relative to the directory. They run in the [`harness`](crate::harness)
+    /// via [`run_with`](crate::harness::run_with), which adds them to its reports.
+    ///
+    /// This requires the `harness` feature.
+    ///
+    /// Panics if the directory doesn't contain any scenario file.
+    ///
+    /// ```no_run
+    /// use bevy_testing::p::*;
+    /// use bevy_testing::harness::{self, Arguments};
+    /// use bevy_testing::ScenarioRunner;
+    ///
+    /// # struct GamePlugin;
+    /// # impl Plugin for GamePlugin {
+    /// #     fn build(&self, _: &mut App) {}
+    /// # }
+    /// fn main() {
+    ///     let scenarios = ScenarioRunner::new()
+    ///         .plugin("game", |app| {
+    ///             app.add_plugins(GamePlugin);
+    ///         })
+    ///         .trials("tests/scenarios");
+    ///     harness::run_with(&Arguments::from_args(), scenarios).exit();
+    /// }
+    /// ```
+    #[cfg(feature = "harness")]
+    pub fn trials(self, dir: impl AsRef<Path>) -> Vec<Trial> {
+        let dir = dir.as_ref();
+        let runner = Arc::new(self);
+        scenario_files(dir)
+            .into_iter()
+            .map(|path| {
+                let name = path
+                    .strip_prefix(dir)
+                    .unwrap_or(&path)
+                    .display()
+                    .to_string();
+                let runner = runner.clone();
+                harness::trial(name, false, None, move || runner.run_file(&path))
+            })
+            .collect()
+    }
+}
+
+/// Finds the scenario files in the directory, sorted by path, and panics if there are none.
+fn scenario_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files);
+    if files.is_empty() {
+        panic!("no *.scenario.ron files were found in {}", dir.display());
+    }
+    files.sort();
+    files
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = fs::read_dir(dir)
+        .unwrap_or_else(|err| panic!("failed to read directory {}: {err}", dir.display()));
+    for entry in entries {
+        let path = entry
+            .unwrap_or_else(|err| panic!("failed to read directory {}: {err}", dir.display()))
+            .path();
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else if path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(".scenario.ron"))
+        {
+            files.push(path);
+        }
+    }
+}
+
+/// A parsed scenario, with the app built from its plugins.
+struct ScenarioFile {
+    app: App,
+    spawn: Vec<EntityDef>,
+    steps: Vec<FileStep>,
+}
+
+/// A component type, as named in a scenario file.
+#[derive(Clone)]
+struct ComponentType {
+    path: String,
+    reflect: ReflectComponent,
+}
+
+enum FileStep {
+    Update(u32),
+    Press(KeyCode),
+    Release(KeyCode),
+    Expect {
+        entity: String,
+        component: ComponentType,
+        value: Box<dyn Reflect>,
+    },
+    Has {
+        entity: String,
+        component: ComponentType,
+    },
+    Missing {
+        entity: String,
+        component: ComponentType,
+    },
+}
+
+impl fmt::Display for FileStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileStep::Update(frames) => write!(f, "Update({frames})"),
+            FileStep::Press(key) => write!(f, "Press({key:?})"),
+            FileStep::Release(key) => write!(f, "Release({key:?})"),
+            FileStep::Expect {
+                entity, component, ..
+            } => write!(f, "Expect({entity}, {})", component.path),
+            FileStep::Has { entity, component } => write!(f, "Has({entity}, {})", component.path),
+            FileStep::Missing { entity, component } => {
+                write!(f, "Missing({entity}, {})", component.path)
+            }
+        }
+    }
+}
+
+impl ScenarioFile {
+    fn run(mut self, name: &str) {
+        self.app.init_resource::<ButtonInput<KeyCode>>();
+        let registry = self.app.world().resource::<AppTypeRegistry>().clone();
+        let mut ids = NamedEntities::default();
+        for (entity_name, components) in &self.spawn {
+            let mut entity = self.app.world_mut().spawn(Name::new(entity_name.clone()));
+            for (component, value) in components {
+                component
+                    .reflect
+                    .insert(&mut entity, value.as_ref(), &registry.read());
+            }
+            ids.insert(entity_name.clone(), entity.id());
+        }
+
+        for (i, step) in self.steps.iter().enumerate() {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                run_step(&mut self.app, &ids, step);
+            }));
+            if let Err(payload) = result {
+                eprintln!();
+                eprintln!(
+                    "{}",
+                    format!("The scenario \"{name}\" failed at step {}.", i + 1).red()
+                );
+                for (j, step) in self.steps[..=i].iter().enumerate() {
+                    if j == i {
+                        eprintln!("  {} {}", "✗".red(), step.to_string().red());
+                    } else {
+                        eprintln!("  {} {}", "✓".green(), step.to_string().bright_black());
+                    }
+                }
+                panic::resume_unwind(payload);
+            }
+        }
+    }
+}
+
+fn run_step(app: &mut App, ids: &NamedEntities, step: &FileStep) {
+    match step {
+        FileStep::Update(frames) => {
+            for _ in 0..*frames {
+                app.update_once();
+                app.world_mut()
+                    .resource_mut::<ButtonInput<KeyCode>>()
+                    .clear();
+            }
+        }
+        FileStep::Press(key) => app
+            .world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(*key),
+        FileStep::Release(key) => app
+            .world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .release(*key),
+        FileStep::Expect {
+            entity,
+            component,
+            value,
+        } => {
+            let found = component.reflect.reflect(app.world().entity(ids[entity]));
+            let Some(found) = found else {
+                fail_missing(entity, component);
+            };
+            if found.reflect_partial_eq(value.as_ref()) != Some(true) {
+                mismatch(
+                    &format!(
+                        "The component \"{}\" of the entity \"{entity}\" doesn't match.",
+                        component.path
+                    ),
+                    value,
+                    found,
+                );
+            }
+        }
+        FileStep::Has { entity, component } => {
+            if !component.reflect.contains(app.world().entity(ids[entity])) {
+                fail_missing(entity, component);
+            }
+        }
+        FileStep::Missing { entity, component } => {
+            let found = component.reflect.reflect(app.world().entity(ids[entity]));
+            if let Some(found) = found {
+                print_failure(&format!(
+                    "The entity \"{entity}\" has the component \"{}\".",
+                    component.path
+                ));
+                print_debug("Found:", found);
+                panic!("assertion failed");
+            }
+        }
+    }
+}
+
+fn fail_missing(entity: &str, component: &ComponentType) -> ! {
+    print_failure(&format!(
+        "The entity \"{entity}\" doesn't have the component \"{}\".",
+        component.path
+    ));
+    panic!("assertion failed");
+}
+
+/// Looks up a component by its type path or short type path.
+fn find_component<'r, E: de::Error>(
+    registry: &'r TypeRegistry,
+    path: &str,
+) -> Result<(&'r TypeRegistration, ComponentType), E> {
+    let registration = registry
+        .get_with_type_path(path)
+        .or_else(|| registry.get_with_short_type_path(path))
+        .ok_or_else(|| {
+            E::custom(format!(
+                "no type named \"{path}\" is registered, or its short name is ambiguous"
+            ))
+        })?;
+    let reflect = registration
+        .data::<ReflectComponent>()
+        .ok_or_else(|| {
+            E::custom(format!(
+                "\"{path}\" doesn't reflect Component, add `#[reflect(Component)]` to it"
+            ))
+        })?
+        .clone();
+    let component = ComponentType {
+        path: path.to_owned(),
+        reflect,
+    };
+    Ok((registration, component))
+}
+
+/// Deserializes a scenario file, building the app as soon as the plugins are known,
+/// so that the components can be looked up in its type registry.
+struct ScenarioSeed<'a> {
+    runner: &'a ScenarioRunner,
+}
+
+impl<'de> DeserializeSeed<'de> for ScenarioSeed<'_> {
+    type Value = ScenarioFile;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<ScenarioFile, D::Error> {
+        deserializer.deserialize_struct("Scenario", &["plugins", "spawn", "steps"], self)
+    }
+}
+
+impl<'de> Visitor<'de> for ScenarioSeed<'_> {
+    type Value = ScenarioFile;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a scenario with `plugins`, `spawn` and `steps`")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<ScenarioFile, A::Error> {
+        let mut key = map.next_key::<Field>()?.map(|field| field.0);
+        let mut app = App::new();
+        if key.as_deref() == Some("plugins") {
+            for plugin in map.next_value::<Vec<String>>()? {
+                let add = self.runner.plugins.get(&plugin).ok_or_else(|| {
+                    let known = self.runner.plugins.keys().cloned().collect::<Vec<_>>();
+                    de::Error::custom(format!(
+                        "unknown plugin \"{plugin}\", registered plugins are: {}",
+                        known.join(", ")
+                    ))
+                })?;
+                add(&mut app);
+            }
+            key = map.next_key::<Field>()?.map(|field| field.0);
+        }
+
+        let registry = app.world().resource::<AppTypeRegistry>().clone();
+        let registry = registry.read();
+        let mut spawn = Vec::new();
+        let mut steps = Vec::new();
+        while let Some(name) = key {
+            match name.as_str() {
+                "spawn" => {
+                    spawn = map.next_value_seed(ListSeed(SpawnSeed(&registry)))?;
+                }
+                "steps" => {
+                    steps = map.next_value_seed(ListSeed(StepSeed(&registry)))?;
+                }
+                "plugins" => {
+                    return Err(de::Error::custom("`plugins` needs to be the first field"))
+                }
+                _ => {
+                    return Err(de::Error::unknown_field(
+                        &name,
+                        &["plugins", "spawn", "steps"],
+                    ))
+                }
+            }
+            key = map.next_key::<Field>()?.map(|field| field.0);
+        }
+        drop(registry);
+
+        Ok(ScenarioFile { app, spawn, steps })
+    }
+}
+
+/// The name of a struct field or enum variant, which RON only deserializes as an identifier.
+struct Field(String);
+
+impl<'de> Deserialize<'de> for Field {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Field, D::Error> {
+        deserializer.deserialize_identifier(FieldVisitor)
+    }
+}
+
+struct FieldVisitor;
+
+impl<'de> Visitor<'de> for FieldVisitor {
+    type Value = Field;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a field name")
+    }
+
+    fn visit_str<E: de::Error>(self, name: &str) -> Result<Field, E> {
+        Ok(Field(name.to_owned()))
+    }
+}
+
+/// Deserializes a list by deserializing every element with the inner seed.
+struct ListSeed<S>(S);
+
+impl<'de, S: DeserializeSeed<'de> + Copy> DeserializeSeed<'de> for ListSeed<S> {
+    type Value = Vec<S::Value>;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, S: DeserializeSeed<'de> + Copy> Visitor<'de> for ListSeed<S> {
+    type Value = Vec<S::Value>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element_seed(self.0)? {
+            items.push(item);
+        }
+        Ok(items)
+    }
+}
+
+/// Deserializes an entity, e.g. `(name: "player", components: { "Health": (100) })`.
+#[derive(Clone, Copy)]
+struct SpawnSeed<'r>(&'r TypeRegistry);
+
+impl<'de> DeserializeSeed<'de> for SpawnSeed<'_> {
+    type Value = EntityDef;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_struct("Entity", &["name", "components"], self)
+    }
+}
+
+impl<'de> Visitor<'de> for SpawnSeed<'_> {
+    type Value = EntityDef;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an entity with a `name` and `components`")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut name = None;
+        let mut components = Vec::new();
+        while let Some(Field(key)) = map.next_key()? {
+            match key.as_str() {
+                "name" => name = Some(map.next_value()?),
+                "components" => components = map.next_value_seed(ComponentsSeed(self.0))?,
+                _ => return Err(de::Error::unknown_field(&key, &["name", "components"])),
+            }
+        }
+        let name = name.ok_or_else(|| de::Error::missing_field("name"))?;
+        Ok((name, components))
+    }
+}
+
+/// Deserializes a map from component names to their values.
+struct ComponentsSeed<'r>(&'r TypeRegistry);
+
+impl<'de> DeserializeSeed<'de> for ComponentsSeed<'_> {
+    type Value = Vec<(ComponentType, Box<dyn Reflect>)>;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ComponentsSeed<'_> {
+    type Value = Vec<(ComponentType, Box<dyn Reflect>)>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map from component names to values")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut components = Vec::new();
+        while let Some(path) = map.next_key::<String>()? {
+            let (registration, component) = find_component(self.0, &path)?;
+            let value = map.next_value_seed(TypedReflectDeserializer::new(registration, self.0))?;
+            components.push((component, value));
+        }
+        Ok(components)
+    }
+}
+
+/// Deserializes a step, e.g. `Update(5)`.
+#[derive(Clone, Copy)]
+struct StepSeed<'r>(&'r TypeRegistry);
+
+const STEPS: &[&str] = &["Update", "Press", "Release", "Expect", "Has", "Missing"];
+
+impl<'de> DeserializeSeed<'de> for StepSeed<'_> {
+    type Value = FileStep;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<FileStep, D::Error> {
+        deserializer.deserialize_enum("Step", STEPS, self)
+    }
+}
+
+impl<'de> Visitor<'de> for StepSeed<'_> {
+    type Value = FileStep;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a step")
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<FileStep, A::Error> {
+        let (Field(name), variant) = data.variant()?;
+        let key = |variant: A::Variant| {
+            let name = variant.newtype_variant::<String>()?;
+            // key codes are matched by their variant name via reflection
+            let known = match KeyCode::type_info() {
+                TypeInfo::Enum(info) => info.contains_variant(&name),
+                _ => false,
+            };
+            let key = DynamicEnum::new(name.as_str(), DynamicVariant::Unit);
+            known
+                .then(|| KeyCode::from_reflect(&key))
+                .flatten()
+                .ok_or_else(|| de::Error::custom(format!("unknown key code \"{name}\"")))
+        };
+
+        match name.as_str() {
+            "Update" => Ok(FileStep::Update(variant.newtype_variant()?)),
+            "Press" => Ok(FileStep::Press(key(variant)?)),
+            "Release" => Ok(FileStep::Release(key(variant)?)),
+            "Expect" | "Has" | "Missing" => {
+                let fields: &[&str] = match name.as_str() {
+                    "Expect" => &["entity", "component", "value"],
+                    _ => &["entity", "component"],
+                };
+                let check = variant.struct_variant(
+                    fields,
+                    CheckSeed {
+                        registry: self.0,
+                        fields,
+                    },
+                )?;
+                let (entity, component) = (check.entity, check.component);
+                Ok(match (name.as_str(), check.value) {
+                    ("Expect", Some(value)) => FileStep::Expect {
+                        entity,
+                        component,
+                        value,
+                    },
+                    ("Expect", None) => return Err(de::Error::missing_field("value")),
+                    ("Has", _) => FileStep::Has { entity, component },
+                    _ => FileStep::Missing { entity, component },
+                })
+            }
+            _ => Err(de::Error::unknown_variant(&name, STEPS)),
+        }
+    }
+}
+
+/// The fields of the `Expect`, `Has` and `Missing` steps.
+struct Check {
+    entity: String,
+    component: ComponentType,
+    value: Option<Box<dyn Reflect>>,
+}
+
+struct CheckSeed<'r> {
+    registry: &'r TypeRegistry,
+    fields: &'static [&'static str],
+}
+
+impl<'de> Visitor<'de> for CheckSeed<'_> {
+    type Value = Check;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the fields {}", self.fields.join(", "))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Check, A::Error> {
+        let mut entity = None;
+        let mut component = None;
+        let mut value = None;
+        while let Some(Field(key)) = map.next_key()? {
+            match key.as_str() {
+                "entity" => entity = Some(map.next_value()?),
+                "component" => {
+                    let path = map.next_value::<String>()?;
+                    component = Some(find_component(self.registry, &path)?);
+                }
+                "value" if self.fields.contains(&"value") => {
+                    let Some((registration, _)) = &component else {
+                        return Err(de::Error::custom(
+                            "`component` needs to come before `value`",
+                        ));
+                    };
+                    value = Some(map.next_value_seed(TypedReflectDeserializer::new(
+                        registration,
+                        self.registry,
+                    ))?);
+                }
+                _ => return Err(de::Error::unknown_field(&key, self.fields)),
+            }
+        }
+        Ok(Check {
+            entity: entity.ok_or_else(|| de::Error::missing_field("entity"))?,
+            component: component
+                .ok_or_else(|| de::Error::missing_field("component"))?
+                .1,
+            value,
+        })
+    }
+}
@@ -0,0 +1,375 @@
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use std::{
+    any::Any,
+    fmt::Debug,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use colored::Colorize;
+
+use crate::{print_debug, FuzzSystemOrder};
+
+/// A test written as a sequence of labeled steps, created via [`scenario`].
+/// All steps share one [`App`] and run as soon as they are added.
+///
+/// If a step panics, the scenario's name and the labels of all steps up to the failing one are
+/// printed.
+///
+/// Steps may return a value, which is ignored. Values borrowing the app, such as the tests
+/// returned by [`App::query`], can't be returned, so those steps need to be wrapped in a block.
+///
+/// ```
+/// use bevy_testing::p::*;
+///
+/// #[derive(Component, Debug, PartialEq)]
+/// struct Health(u32);
+/// #[derive(Component)]
+/// struct Falling;
+///
+/// fn fall_damage(mut commands: Commands, mut query: Query<(Entity, &mut Health), With<Falling>>) {
+///     for (entity, mut health) in &mut query {
+///         health.0 -= 10;
+///         commands.entity(entity).remove::<Falling>();
+///     }
+/// }
+///
+/// scenario("player takes fall damage")
+///     .given("fall damage is applied", |app| {
+///         app.add_systems(Update, fall_damage);
+///     })
+///     .and("a falling player with 100 hp", |app| {
+///         app.spawn((Health(100), Falling)).id()
+///     })
+///     .when("5 frames pass", |app| app.update_n_times(5))
+///     .then("the player took damage once", |app| {
+///         app.query::<&Health>().has(&Health(90));
+///     });
+/// ```
+///
+/// A failing step is reported along with the steps before it:
+///
+/// ```should_panic
+/// use bevy_testing::p::*;
+///
+/// #[derive(Resource)]
+/// struct Count(u32);
+///
+/// scenario("counting")
+///     .given("a count of 1", |app| {
+///         app.insert_resource(Count(1));
+///     })
+///     .when("it is doubled", |app| app.world_mut().resource_mut::<Count>().0 *= 2)
+///     .then("it is 3", |app| assert_eq!(app.world().resource::<Count>().0, 3));
+/// ```
+///
+/// ```text
+/// The scenario "counting" failed at step 3.
+///   ✓ Given a count of 1
+///   ✓ When it is doubled
+///   ✗ Then it is 3
+/// ```
+///
+/// It can also run the same test against many app instances via [`Scenario::table`].
+pub struct Scenario {
+    name: String,
+    app: App,
+    steps: Vec<(Step, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Given,
+    When,
+    Then,
+}
+
+impl Step {
+    fn as_str(self) -> &'static str {
+        match self {
+            Step::Given => "Given",
+            Step::When => "When",
+            Step::Then => "Then",
+        }
+    }
+}
+
+/// Creates a [`Scenario`] with the given name and an empty [`App`].
+pub fn scenario(name: impl Into<String>) -> Scenario {
+    Scenario::new(name)
+}
+
+impl Scenario {
+    /// Creates a scenario with the given name and an empty [`App`], same as [`scenario`].
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            app: App::new(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Returns a [`ScenarioTable`] which runs a test once per row, each with a fresh [`App`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    /// use bevy_testing::Scenario;
+    ///
+    /// #[derive(Component, Debug, PartialEq)]
+    /// struct Health(u32);
+    ///
+    /// Scenario::table([(10u32, 3, 7), (10, 10, 0), (5, 8, 0)])
+    ///     .run(|app, (health, damage, left)| {
+    ///         app.spawn(Health(health.saturating_sub(*damage)));
+    ///         app.update_once();
+    ///
+    ///         app.query::<&Health>()
+    ///             .matches(vec![&Health(*left)]);
+    ///     });
+    /// ```
+    pub fn table<R>(rows: impl IntoIterator<Item = R>) -> ScenarioTable<R> {
+        ScenarioTable {
+            rows: rows.into_iter().collect(),
+            setup: None,
+            parallel: false,
+        }
+    }
+
+    /// Returns a [`FuzzSystemOrder`] which runs a test `runs` times, each with a fresh [`App`]
+    /// and a different order of the systems which access the same data without being ordered.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    /// use bevy_testing::Scenario;
+    ///
+    /// #[derive(Component, Debug, PartialEq)]
+    /// struct Health(u32);
+    ///
+    /// fn regenerate(mut query: Query<&mut Health>) {
+    ///     for mut health in &mut query {
+    ///         health.0 = (health.0 + 10).min(100);
+    ///     }
+    /// }
+    ///
+    /// fn poison(mut query: Query<&mut Health>) {
+    ///     for mut health in &mut query {
+    ///         health.0 -= 5;
+    ///     }
+    /// }
+    ///
+    /// Scenario::fuzz_system_order(7, 8).run(|app| {
+    ///     app.add_systems(Update, (regenerate, poison));
+    ///     app.spawn(Health(50));
+    ///     app.update_once();
+    ///
+    ///     app.query::<&Health>()
+    ///         .matches(vec![&Health(55)]);
+    /// });
+    /// ```
+    pub fn fuzz_system_order(seed: u64, runs: u32) -> FuzzSystemOrder {
+        FuzzSystemOrder { seed, runs }
+    }
+
+    /// Runs a step which sets up the app, e.g. by adding plugins or spawning entities.
+    pub fn given<T>(self, label: impl Into<String>, step: impl FnOnce(&mut App) -> T) -> Self {
+        self.step(Step::Given, label.into(), step)
+    }
+
+    /// Runs a step which acts on the app, e.g. by updating it or sending input.
+    pub fn when<T>(self, label: impl Into<String>, step: impl FnOnce(&mut App) -> T) -> Self {
+        self.step(Step::When, label.into(), step)
+    }
+
+    /// Runs a step which checks the outcome, e.g. via [`App::query`].
+    pub fn then<T>(self, label: impl Into<String>, step: impl FnOnce(&mut App) -> T) -> Self {
+        self.step(Step::Then, label.into(), step)
+    }
+
+    /// Runs another step of the same kind as the previous one.
+    ///
+    /// Panics if this is the first step.
+    pub fn and<T>(self, label: impl Into<String>, step: impl FnOnce(&mut App) -> T) -> Self {
+        let (kind, _) = *self
+            .steps
+            .last()
+            .expect("`Scenario::and` needs to follow another step");
+        self.step(kind, label.into(), step)
+    }
+
+    /// Returns the app, e.g. to continue testing it without steps.
+    pub fn into_app(self) -> App {
+        self.app
+    }
+
+    fn step<T>(mut self, kind: Step, label: String, step: impl FnOnce(&mut App) -> T) -> Self {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            step(&mut self.app);
+        }));
+        self.steps.push((kind, label));
+
+        if let Err(payload) = result {
+            eprintln!();
+            eprintln!(
+                "{}",
+                format!(
+                    "The scenario \"{}\" failed at step {}.",
+                    self.name,
+                    self.steps.len()
+                )
+                .red()
+            );
+            for (i, (kind, label)) in self.steps.iter().enumerate() {
+                let previous = i.checked_sub(1).map(|i| self.steps[i].0);
+                let keyword = if previous == Some(*kind) {
+                    "And"
+                } else {
+                    kind.as_str()
+                };
+                let line = format!("{keyword} {label}");
+                if i + 1 == self.steps.len() {
+                    eprintln!("  {} {}", "✗".red(), line.red());
+                } else {
+                    eprintln!("  {} {}", "✓".green(), line.bright_black());
+                }
+            }
+            panic::resume_unwind(payload);
+        }
+
+        self
+    }
+}
+
+type Setup = Box<dyn Fn(&mut App) + Sync>;
+
+/// A test which runs once per row, created via [`Scenario::table`].
+///
+/// All rows run even if some of them fail. Afterwards, every failed row is reported along with its
+/// parameters.
+pub struct ScenarioTable<R> {
+    rows: Vec<R>,
+    setup: Option<Setup>,
+    parallel: bool,
+}
+
+impl<R: Debug + Sync> ScenarioTable<R> {
+    /// Sets a function which prepares every app before the test runs, e.g. by adding plugins.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    /// use bevy_testing::Scenario;
+    ///
+    /// #[derive(Resource)]
+    /// struct Gravity(f32);
+    ///
+    /// Scenario::table([1.0, 9.81])
+    ///     .setup(|app| {
+    ///         app.insert_resource(Gravity(0.0));
+    ///     })
+    ///     .run(|app, gravity| {
+    ///         app.world_mut().resource_mut::<Gravity>().0 = *gravity;
+    ///         assert!(app.world().resource::<Gravity>().0 > 0.0);
+    ///     });
+    /// ```
+    pub fn setup(mut self, setup: impl Fn(&mut App) + Sync + 'static) -> Self {
+        self.setup = Some(Box::new(setup));
+        self
+    }
+
+    /// Runs the rows on multiple threads instead of one after another.
+    /// The output of failing assertions may then be interleaved, but the final report is not.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    /// use bevy_testing::Scenario;
+    ///
+    /// Scenario::table(0..20)
+    ///     .parallel()
+    ///     .run(|app, row| {
+    ///         app.update_n_times(*row);
+    ///     });
+    /// ```
+    pub fn parallel(mut self) -> Self {
+        self.parallel = true;
+        self
+    }
+
+    /// Runs the test once per row, each with a fresh [`App`], and panics if any row failed.
+    ///
+    /// ```should_panic
+    /// use bevy_testing::p::*;
+    /// use bevy_testing::Scenario;
+    ///
+    /// Scenario::table([1, 2, 3, 4])
+    ///     .run(|_, row| assert!(row % 2 == 0, "{row} is odd"));
+    /// ```
+    pub fn run(self, test: impl Fn(&mut App, &R) + Sync) {
+        let run_row = |index: usize| {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                let mut app = App::new();
+                if let Some(setup) = &self.setup {
+                    setup(&mut app);
+                }
+                test(&mut app, &self.rows[index]);
+            }));
+            let message = panic_message(result.err()?);
+            // labels the assertion output above
+            eprintln!("{}", format!("(row {index})").bright_black());
+            Some((index, message))
+        };
+
+        let mut failures = if self.parallel {
+            let next = AtomicUsize::new(0);
+            let failures = Mutex::new(Vec::new());
+            let threads = thread::available_parallelism().map_or(1, |n| n.get());
+            thread::scope(|scope| {
+                for _ in 0..threads.min(self.rows.len()) {
+                    scope.spawn(|| loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        if index >= self.rows.len() {
+                            break;
+                        }
+                        if let Some(failure) = run_row(index) {
+                            failures.lock().unwrap().push(failure);
+                        }
+                    });
+                }
+            });
+            failures.into_inner().unwrap()
+        } else {
+            (0..self.rows.len()).filter_map(run_row).collect()
+        };
+        failures.sort_by_key(|(index, _)| *index);
+
+        if failures.is_empty() {
+            return;
+        }
+        eprintln!();
+        eprintln!(
+            "{}",
+            format!("{} of {} rows failed.", failures.len(), self.rows.len()).red()
+        );
+        for (index, message) in failures {
+            eprintln!();
+            eprintln!("{}", format!("Row {index}:").bright_black());
+            print_debug("Given:", &self.rows[index]);
+            eprintln!("{} {message}", "Panic:".bright_black());
+        }
+        panic!("assertion failed");
+    }
+}
+
+pub(crate) fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "<unknown>".to_owned()
+    }
+}
@@ -0,0 +1,498 @@
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use std::{
+    any::TypeId,
+    collections::{HashMap, HashSet},
+    fmt::Write as _,
+};
+
+use bevy::{
+    ecs::schedule::{NodeId, ScheduleGraph},
+    utils::get_short_name,
+};
+use colored::Colorize;
+
+use crate::{print_failure, system_info};
+
+/// A struct to perform tests on the graph of a built schedule, which is created via [`App::schedule`].
+///
+/// Unlike [`App::systems`], this only looks at the ordering constraints and sets the systems
+/// were added with, not at the order they happened to run in.
+/// On failure, the systems, orderings and sets of the schedule are printed.
+///
+/// ```
+/// use bevy_testing::p::*;
+///
+/// #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+/// struct Physics;
+///
+/// fn input() {}
+/// fn gravity() {}
+/// fn collisions() {}
+///
+/// let mut app = App::new();
+/// app.configure_sets(Update, Physics.after(input));
+/// app.add_systems(Update, input);
+/// app.add_systems(Update, (gravity, collisions).chain().in_set(Physics));
+///
+/// app.schedule(Update)
+///     .system_count(3)
+///     .in_set(collisions, Physics)
+///     .orders(input, collisions)
+///     .orders(gravity, collisions);
+/// ```
+pub struct AssertSchedule<'a> {
+    pub(crate) schedule: &'a Schedule,
+    pub(crate) invert: bool,
+}
+
+impl<'a> AssertSchedule<'a> {
+    /// Returns an inverted [`AssertSchedule`].
+    /// When chaining methods,
+    /// the inverted state gets reset after every method.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// fn input() {}
+    /// fn render() {}
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(Update, (input, render));
+    ///
+    /// app.schedule(Update)
+    ///     .not().orders(input, render)
+    ///     .not().system_count(0);
+    /// ```
+    #[allow(clippy::should_implement_trait)] // users should not need to import std::ops::Not
+    pub fn not(mut self) -> Self {
+        self.invert = !self.invert;
+        self
+    }
+
+    /// Checks if the schedule contains exactly `given` systems.
+    /// The `apply_deferred` systems inserted by bevy are not counted.
+    ///
+    /// This can be inverted via [`Self::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// fn input() {}
+    /// fn render() {}
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(Update, (input, render));
+    ///
+    /// app.schedule(Update)
+    ///     .system_count(2);
+    /// ```
+    pub fn system_count(self, given: usize) -> Self {
+        let found = self.systems().len();
+        if self.invert {
+            if found == given {
+                self.fail(&format!("The schedule contains {given} systems."));
+            }
+            return self.reset_invert();
+        }
+
+        if found != given {
+            self.fail(&format!(
+                "The schedule contains {found} systems instead of {given}."
+            ));
+        }
+
+        self
+    }
+
+    /// Checks if the schedule contains the system.
+    ///
+    /// This can be inverted via [`Self::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// fn input() {}
+    /// fn render() {}
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(Update, input);
+    ///
+    /// app.schedule(Update)
+    ///     .contains(input)
+    ///     .not().contains(render);
+    /// ```
+    pub fn contains<M>(self, system: impl IntoSystem<(), (), M>) -> Self {
+        let (type_id, name) = system_info(system);
+        let found = !self.nodes_of(type_id).is_empty();
+        if self.invert {
+            if found {
+                self.fail(&format!("The schedule contains the system \"{name}\"."));
+            }
+            return self.reset_invert();
+        }
+
+        if !found {
+            self.fail(&format!(
+                "The schedule doesn't contain the system \"{name}\"."
+            ));
+        }
+
+        self
+    }
+
+    /// Checks if `a` is ordered before `b` by explicit constraints, such as `before`, `after`,
+    /// `chain` or the ordering of sets containing them, possibly through other systems and sets.
+    ///
+    /// This can be inverted via [`Self::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// fn input() {}
+    /// fn physics() {}
+    /// fn render() {}
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(Update, (input, physics.after(input), render.after(physics)));
+    ///
+    /// app.schedule(Update)
+    ///     .orders(input, render)
+    ///     .not().orders(render, input);
+    /// ```
+    pub fn orders<M1, M2>(
+        self,
+        a: impl IntoSystem<(), (), M1>,
+        b: impl IntoSystem<(), (), M2>,
+    ) -> Self {
+        let (a, a_name) = system_info(a);
+        let (b, b_name) = system_info(b);
+        let (a_nodes, b_nodes) = (self.nodes_of(a), self.nodes_of(b));
+        for (node, name) in [(&a_nodes, &a_name), (&b_nodes, &b_name)] {
+            if node.is_empty() {
+                self.fail(&format!(
+                    "The schedule doesn't contain the system \"{name}\"."
+                ));
+            }
+        }
+        let found = a_nodes.iter().all(|a| {
+            let after = after(self.graph(), *a);
+            b_nodes.iter().all(|b| after.contains(b))
+        });
+
+        if self.invert {
+            if found {
+                self.fail(&format!(
+                    "The system \"{a_name}\" is ordered before \"{b_name}\"."
+                ));
+            }
+            return self.reset_invert();
+        }
+
+        if !found {
+            self.fail(&format!(
+                "The system \"{a_name}\" is not ordered before \"{b_name}\"."
+            ));
+        }
+
+        self
+    }
+
+    /// Checks if the system is part of the set, directly or through a nested set.
+    ///
+    /// This can be inverted via [`Self::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+    /// enum Game {
+    ///     Logic,
+    ///     Physics,
+    /// }
+    ///
+    /// fn gravity() {}
+    ///
+    /// let mut app = App::new();
+    /// app.configure_sets(Update, Game::Physics.in_set(Game::Logic));
+    /// app.add_systems(Update, gravity.in_set(Game::Physics));
+    ///
+    /// app.schedule(Update)
+    ///     .in_set(gravity, Game::Physics)
+    ///     .in_set(gravity, Game::Logic);
+    /// ```
+    pub fn in_set<M>(self, system: impl IntoSystem<(), (), M>, set: impl SystemSet) -> Self {
+        let (type_id, name) = system_info(system);
+        let set = set.intern();
+        let set_node = self
+            .graph()
+            .system_sets()
+            .find(|(_, other, _)| other.as_dyn_eq().dyn_eq(set.as_dyn_eq()))
+            .map(|(id, _, _)| id);
+        let found = set_node.is_some_and(|set_node| {
+            let members = descendants(self.graph(), set_node);
+            let nodes = self.nodes_of(type_id);
+            !nodes.is_empty() && nodes.iter().all(|node| members.contains(node))
+        });
+
+        if self.invert {
+            if found {
+                self.fail(&format!("The system \"{name}\" is in the set \"{set:?}\"."));
+            }
+            return self.reset_invert();
+        }
+
+        if !found {
+            self.fail(&format!(
+                "The system \"{name}\" is not in the set \"{set:?}\"."
+            ));
+        }
+
+        self
+    }
+
+    /// Prints the systems, orderings and sets of the schedule to stderr,
+    /// which can be useful while writing a test.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// fn input() {}
+    /// fn render() {}
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(Update, (input, render).chain());
+    ///
+    /// app.schedule(Update).print();
+    /// ```
+    pub fn print(self) -> Self {
+        eprintln!("{}", self.text());
+        self
+    }
+
+    /// Returns the graph of the schedule in the DOT format, which can be rendered by Graphviz.
+    /// Ordering constraints are solid edges, and set membership is dashed.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// fn input() {}
+    /// fn render() {}
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(Update, (input, render).chain());
+    ///
+    /// let dot = app.schedule(Update).to_dot();
+    /// assert!(dot.contains("\"input\" -> \"render\""));
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut out = format!(
+            "digraph {} {{\n",
+            dot_id(&format!("{:?}", self.schedule.label()))
+        );
+        for (_, name) in self.systems() {
+            writeln!(out, "    {};", dot_id(&name)).unwrap();
+        }
+        for (from, to) in self.orderings() {
+            writeln!(out, "    {} -> {};", dot_id(&from), dot_id(&to)).unwrap();
+        }
+        for (set, members) in self.sets() {
+            writeln!(out, "    {} [shape=box];", dot_id(&set)).unwrap();
+            for member in members {
+                writeln!(
+                    out,
+                    "    {} -> {} [style=dashed];",
+                    dot_id(&set),
+                    dot_id(&member)
+                )
+                .unwrap();
+            }
+        }
+        out += "}\n";
+        out
+    }
+
+    fn graph(&self) -> &'a ScheduleGraph {
+        self.schedule.graph()
+    }
+
+    /// The systems of the schedule in the order of execution, without `apply_deferred`.
+    fn systems(&self) -> Vec<(NodeId, String)> {
+        let apply_deferred = IntoSystem::system_type_id(&apply_deferred);
+        self.schedule
+            .systems()
+            .expect("the schedule was initialized")
+            .filter(|(_, system)| system.type_id() != apply_deferred)
+            .map(|(id, system)| (id, get_short_name(&system.name())))
+            .collect()
+    }
+
+    fn nodes_of(&self, type_id: TypeId) -> Vec<NodeId> {
+        self.schedule
+            .systems()
+            .expect("the schedule was initialized")
+            .filter(|(_, system)| system.type_id() == type_id)
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// The name of a system or set. Sets of a single system type are named after the system.
+    fn name(&self, node: NodeId) -> String {
+        if let NodeId::System(_) = node {
+            let systems = self
+                .schedule
+                .systems()
+                .expect("the schedule was initialized");
+            return systems
+                .filter(|(id, _)| *id == node)
+                .map(|(_, system)| get_short_name(&system.name()))
+                .next()
+                .unwrap_or_else(|| format!("{node:?}"));
+        }
+
+        let set = self.graph().set_at(node);
+        if set.system_type().is_some() {
+            let children = children(self.graph());
+            if let Some(child) = children.get(&node).and_then(|children| children.first()) {
+                return self.name(*child);
+            }
+        }
+        format!("{set:?}")
+    }
+
+    /// The explicit ordering constraints, as pairs of names.
+    fn orderings(&self) -> Vec<(String, String)> {
+        self.graph()
+            .dependency()
+            .graph()
+            .all_edges()
+            .map(|(from, to, _)| (self.name(from), self.name(to)))
+            .collect()
+    }
+
+    /// The named sets along with the names of their direct members.
+    fn sets(&self) -> Vec<(String, Vec<String>)> {
+        let children = children(self.graph());
+        self.graph()
+            .system_sets()
+            .filter(|(_, set, _)| set.system_type().is_none() && !set.is_anonymous())
+            .map(|(id, set, _)| {
+                let members = children
+                    .get(&id)
+                    .into_iter()
+                    .flatten()
+                    .map(|child| self.name(*child))
+                    .collect();
+                (format!("{set:?}"), members)
+            })
+            .collect()
+    }
+
+    /// Renders the schedule as a list of systems, orderings and sets.
+    fn text(&self) -> String {
+        let mut out = format!(
+            "{}",
+            format!("Schedule {:?}:", self.schedule.label()).bright_black()
+        );
+        out += &format!("\n  {}", "Systems:".bright_black());
+        for (_, name) in self.systems() {
+            out += &format!("\n    {name}");
+        }
+        let orderings = self.orderings();
+        if !orderings.is_empty() {
+            out += &format!("\n  {}", "Orderings:".bright_black());
+            for (from, to) in orderings {
+                out += &format!("\n    {from} -> {to}");
+            }
+        }
+        let sets = self.sets();
+        if !sets.is_empty() {
+            out += &format!("\n  {}", "Sets:".bright_black());
+            for (set, members) in sets {
+                out += &format!("\n    {set}: {}", members.join(", "));
+            }
+        }
+        out
+    }
+
+    fn fail(&self, message: &str) -> ! {
+        print_failure(message);
+        eprintln!("{}", self.text());
+        panic!("assertion failed");
+    }
+
+    fn reset_invert(mut self) -> Self {
+        self.invert = false;
+        self
+    }
+}
+
+/// The children of every set.
+pub(crate) fn children(graph: &ScheduleGraph) -> HashMap<NodeId, Vec<NodeId>> {
+    let mut children = HashMap::<_, Vec<_>>::new();
+    for (parent, child, _) in graph.hierarchy().graph().all_edges() {
+        children.entry(parent).or_default().push(child);
+    }
+    children
+}
+
+fn descendants(graph: &ScheduleGraph, set: NodeId) -> HashSet<NodeId> {
+    let children = children(graph);
+    let mut found = HashSet::new();
+    let mut stack = vec![set];
+    while let Some(node) = stack.pop() {
+        for child in children.get(&node).into_iter().flatten() {
+            if found.insert(*child) {
+                stack.push(*child);
+            }
+        }
+    }
+    found
+}
+
+/// All nodes which are ordered after the node. If a set is ordered after it,
+/// so are all of its members, and a node is ordered before everything its sets are.
+pub(crate) fn after(graph: &ScheduleGraph, node: NodeId) -> HashSet<NodeId> {
+    let children = children(graph);
+    let mut parents = HashMap::<_, Vec<_>>::new();
+    for (parent, child, _) in graph.hierarchy().graph().all_edges() {
+        parents.entry(child).or_default().push(parent);
+    }
+    let dependency = graph.dependency().graph();
+    let ancestors = |node: NodeId| {
+        let mut found = vec![node];
+        let mut i = 0;
+        while i < found.len() {
+            for parent in parents.get(&found[i]).into_iter().flatten() {
+                if !found.contains(parent) {
+                    found.push(*parent);
+                }
+            }
+            i += 1;
+        }
+        found
+    };
+
+    let mut after = HashSet::new();
+    let mut stack = ancestors(node)
+        .into_iter()
+        .flat_map(|source| dependency.neighbors(source))
+        .collect::<Vec<_>>();
+    while let Some(node) = stack.pop() {
+        if !after.insert(node) {
+            continue;
+        }
+        stack.extend(children.get(&node).into_iter().flatten());
+        stack.extend(
+            ancestors(node)
+                .into_iter()
+                .flat_map(|source| dependency.neighbors(source)),
+        );
+    }
+    after
+}
+
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,492 @@
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::{
+    app::MainScheduleOrder,
+    ecs::{event::ManualEventReader, schedule::ScheduleLabel},
+    reflect::{
+        serde::{TypedReflectDeserializer, TypedReflectSerializer},
+        DynamicEnum, DynamicVariant, GetTypeRegistration, TypeInfo, TypePath, TypeRegistry, Typed,
+        VariantType,
+    },
+    time::{Real, TimeUpdateStrategy},
+};
+use colored::Colorize;
+use serde::{de::DeserializeSeed, Deserialize, Serialize};
+
+use crate::{hash::world_hash, print_failure, TestRng};
+
+/// A plugin which records a session, so that it can be replayed via [`App::replay`].
+///
+/// Once per update, it records the [`KeyCode`]s and [`MouseButton`]s which were pressed
+/// or released, the events of the types added via [`SessionRecorder::event`], the delta of the
+/// real [`Time`] and the seed of the [`TestRng`]. The session gets written to the file
+/// when the app exits, or via [`App::save_session`], along with a hash of the world
+/// to check the replay against.
+///
+/// This requires the `session` feature.
+///
+/// ```
+/// use bevy_testing::p::*;
+/// use bevy_testing::SessionRecorder;
+///
+/// #[derive(Component, Reflect, Debug, PartialEq)]
+/// #[reflect(Component)]
+/// struct Jumps(u32);
+///
+/// fn jump(keys: Res<ButtonInput<KeyCode>>, mut query: Query<&mut Jumps>) {
+///     if keys.just_pressed(KeyCode::Space) {
+///         for mut jumps in &mut query {
+///             jumps.0 += 1;
+///         }
+///     }
+/// }
+///
+/// fn build() -> App {
+///     let mut app = App::new();
+///     app.register_type::<Jumps>();
+///     app.init_resource::<ButtonInput<KeyCode>>();
+///     app.add_systems(Update, jump);
+///     app.spawn(Jumps(0));
+///     app
+/// }
+///
+/// let path = std::env::temp_dir().join("bevy_testing_session_recorder.ron");
+///
+/// let mut app = build();
+/// app.add_plugins(SessionRecorder::new(&path));
+/// app.update_once();
+/// app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::Space);
+/// app.update_once();
+/// // without the `InputPlugin`, the input has to be cleared manually
+/// app.world_mut().resource_mut::<ButtonInput<KeyCode>>().clear();
+/// app.update_once();
+/// app.save_session();
+///
+/// let mut replay = build();
+/// replay.replay(&path);
+/// replay.query::<&Jumps>().matches(vec![&Jumps(1)]);
+/// ```
+pub struct SessionRecorder {
+    path: PathBuf,
+    events: Vec<fn(&mut App)>,
+}
+
+impl SessionRecorder {
+    /// Creates a recorder which writes the session to the given path.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Also records the events of type `E`, e.g. messages from the network or the UI.
+    ///
+    /// The events are replayed as they were sent, so events which are sent by the systems of
+    /// the app itself would be sent twice.
+    ///
+    /// They are replayed before [`First`] of the update in which they were recorded. Events sent
+    /// after [`PreUpdate`] are recorded with the next update, so in the replay, their readers
+    /// see them one update later.
+    pub fn event<E: Event + FromReflect + TypePath + GetTypeRegistration>(mut self) -> Self {
+        self.events.push(add_channel::<E>);
+        self
+    }
+}
+
+impl Plugin for SessionRecorder {
+    fn build(&self, app: &mut App) {
+        install(app);
+        for add in &self.events {
+            add(app);
+        }
+        app.insert_resource(Recording {
+            path: self.path.clone(),
+            frames: Vec::new(),
+            current: Frame::default(),
+        });
+    }
+}
+
+/// A session, as written to the file.
+#[derive(Serialize, Deserialize)]
+struct SessionFile {
+    seed: Option<u64>,
+    hash: u64,
+    frames: Vec<Frame>,
+}
+
+/// The input of a single update.
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct Frame {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    delta: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pressed: Vec<Button>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    released: Vec<Button>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    events: Vec<(String, String)>,
+}
+
+/// A button, named by its variant.
+#[derive(Serialize, Deserialize, Clone)]
+enum Button {
+    Key(String),
+    Mouse(String),
+}
+
+/// The session being recorded.
+#[derive(Resource)]
+struct Recording {
+    path: PathBuf,
+    frames: Vec<Frame>,
+    current: Frame,
+}
+
+/// The input of the update being replayed.
+#[derive(Resource)]
+struct Replaying(Frame);
+
+/// The event types which are recorded and replayed.
+#[derive(Resource, Default)]
+struct SessionEvents(Vec<Box<dyn Channel>>);
+
+/// Records and sends the events of one type.
+trait Channel: Send + Sync {
+    fn type_path(&self) -> &'static str;
+
+    /// Returns the events sent since the last call, as RON.
+    fn record(&mut self, world: &World, registry: &TypeRegistry) -> Vec<String>;
+
+    fn send(&self, world: &mut World, registry: &TypeRegistry, value: &str) -> Result<(), String>;
+}
+
+struct EventChannel<E: Event> {
+    reader: ManualEventReader<E>,
+}
+
+impl<E: Event + FromReflect + TypePath + GetTypeRegistration> Channel for EventChannel<E> {
+    fn type_path(&self) -> &'static str {
+        E::type_path()
+    }
+
+    fn record(&mut self, world: &World, registry: &TypeRegistry) -> Vec<String> {
+        let Some(events) = world.get_resource::<Events<E>>() else {
+            return Vec::new();
+        };
+        self.reader
+            .read(events)
+            .map(|event| {
+                ron::to_string(&TypedReflectSerializer::new(event, registry))
+                    .unwrap_or_else(|err| panic!("failed to record {}: {err}", E::type_path()))
+            })
+            .collect()
+    }
+
+    fn send(&self, world: &mut World, registry: &TypeRegistry, value: &str) -> Result<(), String> {
+        let registration = registry
+            .get(std::any::TypeId::of::<E>())
+            .ok_or("the type is not registered")?;
+        let mut deserializer = ron::Deserializer::from_str(value).map_err(|err| err.to_string())?;
+        let value = TypedReflectDeserializer::new(registration, registry)
+            .deserialize(&mut deserializer)
+            .map_err(|err| err.to_string())?;
+        let event = E::from_reflect(&*value).ok_or("the value doesn't match the type")?;
+        world.send_event(event);
+        Ok(())
+    }
+}
+
+/// Runs before [`First`], so that replayed events are seen by the same systems as when they
+/// were recorded.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+struct SessionStart;
+
+/// Runs after [`PreUpdate`], once the input of the frame is known.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+struct SessionInput;
+
+/// Runs after [`Last`], once every schedule of the frame is done.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+struct SessionEnd;
+
+fn install(app: &mut App) {
+    if app.world().contains_resource::<SessionEvents>() {
+        return;
+    }
+    app.init_resource::<SessionEvents>();
+    let mut order = app.world_mut().resource_mut::<MainScheduleOrder>();
+    order.insert_before(First, SessionStart);
+    order.insert_after(PreUpdate, SessionInput);
+    order.insert_after(Last, SessionEnd);
+    app.add_systems(SessionStart, replay_events);
+    // a replayed session can be recorded again
+    app.add_systems(SessionInput, (replay_input, record_input).chain());
+    app.add_systems(SessionEnd, end_frame);
+}
+
+fn add_channel<E: Event + FromReflect + TypePath + GetTypeRegistration>(app: &mut App) {
+    install(app);
+    app.register_type::<E>();
+    let mut events = app.world_mut().resource_mut::<SessionEvents>();
+    if events
+        .0
+        .iter()
+        .all(|channel| channel.type_path() != E::type_path())
+    {
+        events.0.push(Box::new(EventChannel::<E> {
+            reader: ManualEventReader::default(),
+        }));
+    }
+}
+
+fn record_input(world: &mut World) {
+    if !world.contains_resource::<Recording>() {
+        return;
+    }
+    let mut pressed = Vec::new();
+    let mut released = Vec::new();
+    if let Some(keys) = world.get_resource::<ButtonInput<KeyCode>>() {
+        pressed.extend(
+            keys.get_just_pressed()
+                .map(|key| Button::Key(button_name(key))),
+        );
+        released.extend(
+            keys.get_just_released()
+                .map(|key| Button::Key(button_name(key))),
+        );
+    }
+    if let Some(buttons) = world.get_resource::<ButtonInput<MouseButton>>() {
+        pressed.extend(
+            buttons
+                .get_just_pressed()
+                .map(|button| Button::Mouse(button_name(button))),
+        );
+        released.extend(
+            buttons
+                .get_just_released()
+                .map(|button| Button::Mouse(button_name(button))),
+        );
+    }
+    let events = world.resource_scope(|world, mut channels: Mut<SessionEvents>| {
+        let registry = world.resource::<AppTypeRegistry>().read();
+        channels
+            .0
+            .iter_mut()
+            .flat_map(|channel| {
+                let type_path = channel.type_path();
+                channel
+                    .record(world, &registry)
+                    .into_iter()
+                    .map(move |value| (type_path.to_owned(), value))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let mut recording = world.resource_mut::<Recording>();
+    recording.current.pressed = pressed;
+    recording.current.released = released;
+    recording.current.events = events;
+}
+
+fn replay_input(world: &mut World) {
+    let Some(Replaying(frame)) = world.remove_resource::<Replaying>() else {
+        return;
+    };
+    if frame
+        .pressed
+        .iter()
+        .chain(&frame.released)
+        .any(|button| matches!(button, Button::Key(_)))
+    {
+        world.init_resource::<ButtonInput<KeyCode>>();
+    }
+    if frame
+        .pressed
+        .iter()
+        .chain(&frame.released)
+        .any(|button| matches!(button, Button::Mouse(_)))
+    {
+        world.init_resource::<ButtonInput<MouseButton>>();
+    }
+    if let Some(mut keys) = world.get_resource_mut::<ButtonInput<KeyCode>>() {
+        keys.clear();
+    }
+    if let Some(mut buttons) = world.get_resource_mut::<ButtonInput<MouseButton>>() {
+        buttons.clear();
+    }
+    for (button, press) in frame
+        .released
+        .iter()
+        .map(|button| (button, false))
+        .chain(frame.pressed.iter().map(|button| (button, true)))
+    {
+        match button {
+            Button::Key(name) => apply(
+                &mut world.resource_mut::<ButtonInput<KeyCode>>(),
+                parse_button(name),
+                press,
+            ),
+            Button::Mouse(name) => apply(
+                &mut world.resource_mut::<ButtonInput<MouseButton>>(),
+                parse_button(name),
+                press,
+            ),
+        }
+    }
+}
+
+fn replay_events(world: &mut World) {
+    let Some(Replaying(frame)) = world.get_resource::<Replaying>() else {
+        return;
+    };
+    let events = frame.events.clone();
+    world.resource_scope(|world, channels: Mut<SessionEvents>| {
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        let registry = registry.read();
+        for (type_path, value) in &events {
+            let channel = channels
+                .0
+                .iter()
+                .find(|channel| channel.type_path() == type_path)
+                .unwrap_or_else(|| {
+                    panic!(
+                        "the session contains events of type {type_path}, \
+                        add it via `App::replay_event` first"
+                    )
+                });
+            channel
+                .send(world, &registry, value)
+                .unwrap_or_else(|err| panic!("failed to replay {type_path} {value}: {err}"));
+        }
+    });
+}
+
+fn end_frame(world: &mut World) {
+    let delta = world.get_resource::<Time<Real>>().map(|time| time.delta());
+    let Some(mut recording) = world.get_resource_mut::<Recording>() else {
+        return;
+    };
+    let mut frame = std::mem::take(&mut recording.current);
+    frame.delta = delta;
+    recording.frames.push(frame);
+
+    let exiting = world
+        .get_resource::<Events<AppExit>>()
+        .is_some_and(|events| !events.is_empty());
+    if exiting {
+        save(world);
+    }
+}
+
+/// Writes the recorded session to its file.
+pub(crate) fn save(world: &mut World) {
+    let hash = world_hash(world);
+    let seed = world.get_resource::<TestRng>().map(TestRng::seed);
+    let recording = world.get_resource::<Recording>().unwrap_or_else(|| {
+        panic!("no session is recorded, add the `SessionRecorder` plugin first")
+    });
+    let file = SessionFile {
+        seed,
+        hash,
+        frames: recording.frames.clone(),
+    };
+    let content = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+        .expect("a session can be serialized");
+    let path = &recording.path;
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)
+            .unwrap_or_else(|err| panic!("failed to create directory {}: {err}", dir.display()));
+    }
+    fs::write(path, content)
+        .unwrap_or_else(|err| panic!("failed to write session {}: {err}", path.display()));
+}
+
+/// Replays the session from the file and checks the hash of the resulting world.
+pub(crate) fn replay(app: &mut App, path: &Path) {
+    let content = fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("failed to read session {}: {err}", path.display()));
+    let file: SessionFile = ron::from_str(&content)
+        .unwrap_or_else(|err| panic!("failed to parse session {}: {err}", path.display()));
+    install(app);
+    if let Some(seed) = file.seed {
+        app.insert_resource(TestRng::new(seed));
+    }
+
+    let frames = file.frames.len();
+    for frame in file.frames {
+        if let Some(delta) = frame.delta {
+            app.insert_resource(TimeUpdateStrategy::ManualDuration(delta));
+        }
+        app.insert_resource(Replaying(frame));
+        app.update_once();
+    }
+
+    let hash = world_hash(app.world());
+    if hash != file.hash {
+        print_failure(&format!(
+            "The world hash after replaying {frames} frames is {hash:016x} instead of {:016x}.",
+            file.hash
+        ));
+        eprintln!(
+            "{}",
+            "The replayed app must be built like the recorded one, and its systems must be \
+            deterministic."
+                .bright_black()
+        );
+        panic!("assertion failed");
+    }
+}
+
+/// Registers an event type for [`replay`].
+pub(crate) fn replay_event<E: Event + FromReflect + TypePath + GetTypeRegistration>(app: &mut App) {
+    add_channel::<E>(app);
+}
+
+/// Returns the name of a button's variant, or its debug output if the variant has fields.
+fn button_name<T: Reflect + std::fmt::Debug>(button: &T) -> String {
+    match button.reflect_ref() {
+        bevy::reflect::ReflectRef::Enum(value) if value.variant_type() == VariantType::Unit => {
+            value.variant_name().to_owned()
+        }
+        _ => format!("{button:?}"),
+    }
+}
+
+fn parse_button<T: FromReflect + Typed>(name: &str) -> T {
+    let known = match T::type_info() {
+        TypeInfo::Enum(info) => info.contains_variant(name),
+        _ => false,
+    };
+    let button = DynamicEnum::new(name, DynamicVariant::Unit);
+    known
+        .then(|| T::from_reflect(&button))
+        .flatten()
+        .unwrap_or_else(|| panic!("unknown button \"{name}\" in the session"))
+}
+
+/// Presses or releases the button, so that it's just pressed or just released afterwards.
+fn apply<T: Copy + Eq + std::hash::Hash + Send + Sync + 'static>(
+    input: &mut ButtonInput<T>,
+    button: T,
+    press: bool,
+) {
+    if press {
+        input.reset(button);
+        input.press(button);
+    } else {
+        if !input.pressed(button) {
+            input.press(button);
+            input.clear_just_pressed(button);
+        }
+        input.release(button);
+    }
+}
@@ -0,0 +1,611 @@
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use std::{
+    any::TypeId,
+    collections::{BTreeMap, HashSet},
+    fmt,
+};
+
+use bevy::{
+    reflect::ReflectRef,
+    time::{Fixed, Real, Virtual},
+};
+use colored::Colorize;
+
+use crate::{golden, print_failure};
+
+/// Chooses which components and resources are captured by a [`WorldSnapshot`].
+///
+/// ```
+/// use bevy_testing::p::*;
+/// use bevy_testing::SnapshotFilter;
+///
+/// #[derive(Component, Reflect)]
+/// #[reflect(Component)]
+/// struct Health(u32);
+///
+/// #[derive(Component, Reflect)]
+/// #[reflect(Component)]
+/// struct Position(f32, f32);
+///
+/// let mut app = App::new();
+/// app.register_type::<Health>();
+/// app.register_type::<Position>();
+/// app.spawn(Health(10));
+/// app.spawn(Position(0.0, 0.0));
+///
+/// let snapshot = app.snapshot_with(&SnapshotFilter::new().component::<Health>());
+/// assert_eq!(snapshot.entity_count(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SnapshotFilter {
+    components: Selection,
+    resources: Selection,
+}
+
+/// The types to capture: every type if `all` is set, and the `chosen` ones in any case.
+#[derive(Debug, Clone, Default)]
+struct Selection {
+    all: bool,
+    chosen: HashSet<TypeId>,
+}
+
+impl Selection {
+    fn all() -> Self {
+        Self {
+            all: true,
+            chosen: HashSet::new(),
+        }
+    }
+
+    fn contains(&self, type_id: TypeId) -> bool {
+        self.all || self.chosen.contains(&type_id)
+    }
+}
+
+impl SnapshotFilter {
+    /// Creates a filter which captures nothing.
+    /// Add components and resources via [`Self::component`] and [`Self::resource`].
+    pub fn new() -> Self {
+        Self {
+            components: Selection::default(),
+            resources: Selection::default(),
+        }
+    }
+
+    /// Creates a filter which captures all reflectable components and resources.
+    /// This is the filter used by [`App::snapshot`].
+    ///
+    /// The [`Time`] resources are left out, since they advance with the real time, unless they
+    /// are chosen explicitly via [`Self::resource`].
+    ///
+    /// ```
+    /// use bevy::time::Real;
+    /// use bevy_testing::p::*;
+    /// use bevy_testing::SnapshotFilter;
+    ///
+    /// let mut app = App::new();
+    /// app.add_plugins(MinimalPlugins);
+    /// app.update_once();
+    /// let registry = app.world().resource::<AppTypeRegistry>().clone();
+    ///
+    /// let all = app.snapshot_with(&SnapshotFilter::all());
+    /// assert!(!all.to_ron(&registry).contains("Time<"));
+    ///
+    /// let with_time = app.snapshot_with(&SnapshotFilter::all().resource::<Time<Real>>());
+    /// assert!(with_time.to_ron(&registry).contains("Time<bevy_time::real::Real>"));
+    /// ```
+    pub fn all() -> Self {
+        Self {
+            components: Selection::all(),
+            resources: Selection::all(),
+        }
+    }
+
+    /// Captures the component `T`.
+    /// Once specific components are chosen, entities without any of them are left out.
+    pub fn component<T: Component>(mut self) -> Self {
+        self.components.chosen.insert(TypeId::of::<T>());
+        self
+    }
+
+    /// Captures the resource `R`.
+    pub fn resource<R: Resource>(mut self) -> Self {
+        self.resources.chosen.insert(TypeId::of::<R>());
+        self
+    }
+
+    /// Captures all reflectable components.
+    pub fn all_components(mut self) -> Self {
+        self.components.all = true;
+        self
+    }
+
+    /// Captures all reflectable resources.
+    pub fn all_resources(mut self) -> Self {
+        self.resources.all = true;
+        self
+    }
+}
+
+/// Returns the resources which are left out when capturing all resources, as they differ
+/// between runs.
+fn time_resources() -> [TypeId; 4] {
+    [
+        TypeId::of::<Time>(),
+        TypeId::of::<Time<Real>>(),
+        TypeId::of::<Time<Virtual>>(),
+        TypeId::of::<Time<Fixed>>(),
+    ]
+}
+
+impl Default for SnapshotFilter {
+    /// Creates a filter which captures nothing, like [`Self::new`].
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A copy of all reflectable components and resources of a world, created via [`App::snapshot`].
+///
+/// Only types which are registered in the [`AppTypeRegistry`] and reflect
+/// [`Component`] or [`Resource`] are captured.
+///
+/// ```
+/// use bevy_testing::p::*;
+///
+/// #[derive(Component, Reflect)]
+/// #[reflect(Component)]
+/// struct Health(u32);
+///
+/// let mut app = App::new();
+/// app.register_type::<Health>();
+/// app.spawn(Health(10));
+/// app.spawn_empty();
+///
+/// let snapshot = app.snapshot();
+/// assert_eq!(snapshot.entity_count(), 2);
+/// ```
+#[derive(Debug)]
+pub struct WorldSnapshot {
+    pub(crate) entities: BTreeMap<Entity, BTreeMap<String, Box<dyn Reflect>>>,
+    pub(crate) names: BTreeMap<Entity, String>,
+    pub(crate) resources: BTreeMap<String, Box<dyn Reflect>>,
+}
+
+impl WorldSnapshot {
+    pub(crate) fn capture(world: &World, filter: &SnapshotFilter) -> Self {
+        let registry = world.resource::<AppTypeRegistry>().read();
+
+        let mut entities = BTreeMap::new();
+        let mut names = BTreeMap::new();
+        for entity_ref in world.iter_entities() {
+            let mut components = BTreeMap::new();
+            for component_id in entity_ref.archetype().components() {
+                let Some(registration) = world
+                    .components()
+                    .get_info(component_id)
+                    .and_then(|info| info.type_id())
+                    .and_then(|type_id| registry.get(type_id))
+                else {
+                    continue;
+                };
+                if !filter.components.contains(registration.type_id()) {
+                    continue;
+                }
+                let Some(value) = registration
+                    .data::<ReflectComponent>()
+                    .and_then(|reflect_component| reflect_component.reflect(entity_ref))
+                else {
+                    continue;
+                };
+                components.insert(
+                    registration.type_info().type_path().to_owned(),
+                    value.clone_value(),
+                );
+            }
+            if components.is_empty() && !filter.components.all {
+                continue;
+            }
+            if let Some(name) = entity_ref.get::<Name>() {
+                names.insert(entity_ref.id(), name.as_str().to_owned());
+            }
+            entities.insert(entity_ref.id(), components);
+        }
+
+        let mut resources = BTreeMap::new();
+        let time_resources = time_resources();
+        for registration in registry.iter() {
+            if !filter.resources.contains(registration.type_id()) {
+                continue;
+            }
+            if time_resources.contains(&registration.type_id())
+                && !filter.resources.chosen.contains(&registration.type_id())
+            {
+                continue;
+            }
+            let Some(value) = registration
+                .data::<ReflectResource>()
+                .and_then(|reflect_resource| reflect_resource.reflect(world))
+            else {
+                continue;
+            };
+            resources.insert(
+                registration.type_info().type_path().to_owned(),
+                value.clone_value(),
+            );
+        }
+
+        Self {
+            entities,
+            names,
+            resources,
+        }
+    }
+
+    /// Returns the amount of entities in the snapshot.
+    pub fn entity_count(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Renders the snapshot as RON, which is the format used by [`App::assert_snapshot`].
+    ///
+    /// Entities are sorted by their [`Name`], unnamed entities first, and then by their
+    /// components, so the output doesn't depend on the order of spawning or on reused entity ids.
+    /// Entity ids are replaced by the position in that order. Entities without any captured
+    /// component are left out.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component, Reflect)]
+    /// #[reflect(Component)]
+    /// struct Health(u32);
+    ///
+    /// #[derive(Component, Reflect)]
+    /// #[reflect(Component)]
+    /// struct Target(Entity);
+    ///
+    /// let mut app = App::new();
+    /// app.register_type::<Health>();
+    /// app.register_type::<Target>();
+    /// let player = app.spawn((Name::new("player"), Health(10))).id();
+    /// app.spawn((Name::new("enemy"), Target(player)));
+    /// app.spawn(Health(1));
+    /// app.spawn(Health(2));
+    ///
+    /// let ron = app.snapshot().to_ron(app.world().resource::<AppTypeRegistry>());
+    /// assert!(ron.contains(r#"name: "enemy""#));
+    /// assert!(ron.contains(r#"Target": (Entity(3))"#));
+    ///
+    /// // the same world, spawned in another order
+    /// let mut other = App::new();
+    /// other.register_type::<Health>();
+    /// other.register_type::<Target>();
+    /// other.spawn(Health(2));
+    /// other.spawn(Health(1));
+    /// let player = other.spawn((Name::new("player"), Health(10))).id();
+    /// other.spawn((Name::new("enemy"), Target(player)));
+    ///
+    /// assert_eq!(other.snapshot().to_ron(other.world().resource::<AppTypeRegistry>()), ron);
+    /// ```
+    pub fn to_ron(&self, registry: &AppTypeRegistry) -> String {
+        golden::to_ron(self, &registry.read())
+    }
+
+    /// Compares this snapshot to a newer one.
+    pub(crate) fn diff(&self, newer: &WorldSnapshot) -> WorldDiff {
+        let mut diff = WorldDiff::default();
+
+        for (entity, components) in &newer.entities {
+            let Some(old_components) = self.entities.get(entity) else {
+                diff.spawned.push(*entity);
+                continue;
+            };
+            for (type_path, value) in components {
+                match old_components.get(type_path) {
+                    None => diff.added.push((*entity, type_path.clone())),
+                    Some(old_value) => diff_values(
+                        String::new(),
+                        old_value.as_ref(),
+                        value.as_ref(),
+                        &mut |field, before, after| {
+                            diff.changed.push(ValueChange {
+                                entity: Some(*entity),
+                                type_path: type_path.clone(),
+                                field,
+                                before,
+                                after,
+                            })
+                        },
+                    ),
+                }
+            }
+            for type_path in old_components.keys() {
+                if !components.contains_key(type_path) {
+                    diff.removed.push((*entity, type_path.clone()));
+                }
+            }
+        }
+        for entity in self.entities.keys() {
+            if !newer.entities.contains_key(entity) {
+                diff.despawned.push(*entity);
+            }
+        }
+
+        for (type_path, value) in &newer.resources {
+            match self.resources.get(type_path) {
+                None => diff.resources_added.push(type_path.clone()),
+                Some(old_value) => diff_values(
+                    String::new(),
+                    old_value.as_ref(),
+                    value.as_ref(),
+                    &mut |field, before, after| {
+                        diff.changed.push(ValueChange {
+                            entity: None,
+                            type_path: type_path.clone(),
+                            field,
+                            before,
+                            after,
+                        })
+                    },
+                ),
+            }
+        }
+        for type_path in self.resources.keys() {
+            if !newer.resources.contains_key(type_path) {
+                diff.resources_removed.push(type_path.clone());
+            }
+        }
+
+        diff.names = self.names.clone();
+        diff.names.extend(newer.names.clone());
+        diff
+    }
+}
+
+/// Compares two reflected values field by field and reports every differing leaf.
+fn diff_values(
+    path: String,
+    before: &dyn Reflect,
+    after: &dyn Reflect,
+    report: &mut impl FnMut(String, String, String),
+) {
+    let join = |field: &str| {
+        if path.is_empty() {
+            field.to_owned()
+        } else {
+            format!("{path}.{field}")
+        }
+    };
+
+    match (before.reflect_ref(), after.reflect_ref()) {
+        (ReflectRef::Struct(a), ReflectRef::Struct(b)) if a.field_len() == b.field_len() => {
+            for (i, field) in a.iter_fields().enumerate() {
+                let name = a.name_at(i).unwrap();
+                match b.field(name) {
+                    Some(other) => diff_values(join(name), field, other, report),
+                    None => report_leaf(path.clone(), before, after, report),
+                }
+            }
+        }
+        (ReflectRef::TupleStruct(a), ReflectRef::TupleStruct(b))
+            if a.field_len() == b.field_len() =>
+        {
+            for (i, (field, other)) in a.iter_fields().zip(b.iter_fields()).enumerate() {
+                diff_values(join(&i.to_string()), field, other, report);
+            }
+        }
+        (ReflectRef::Tuple(a), ReflectRef::Tuple(b)) if a.field_len() == b.field_len() => {
+            for (i, (field, other)) in a.iter_fields().zip(b.iter_fields()).enumerate() {
+                diff_values(join(&i.to_string()), field, other, report);
+            }
+        }
+        (ReflectRef::List(a), ReflectRef::List(b)) if a.len() == b.len() => {
+            for (i, (item, other)) in a.iter().zip(b.iter()).enumerate() {
+                diff_values(format!("{path}[{i}]"), item, other, report);
+            }
+        }
+        (ReflectRef::Array(a), ReflectRef::Array(b)) if a.len() == b.len() => {
+            for (i, (item, other)) in a.iter().zip(b.iter()).enumerate() {
+                diff_values(format!("{path}[{i}]"), item, other, report);
+            }
+        }
+        (ReflectRef::Enum(a), ReflectRef::Enum(b))
+            if a.variant_name() == b.variant_name() && a.field_len() == b.field_len() =>
+        {
+            for i in 0..a.field_len() {
+                let field = join(&a.name_at(i).map_or_else(|| i.to_string(), str::to_owned));
+                diff_values(
+                    field,
+                    a.field_at(i).unwrap(),
+                    b.field_at(i).unwrap(),
+                    report,
+                );
+            }
+        }
+        _ => report_leaf(path, before, after, report),
+    }
+}
+
+fn report_leaf(
+    path: String,
+    before: &dyn Reflect,
+    after: &dyn Reflect,
+    report: &mut impl FnMut(String, String, String),
+) {
+    let before_str = format!("{before:?}");
+    let after_str = format!("{after:?}");
+    let equal = before
+        .reflect_partial_eq(after)
+        .unwrap_or(before_str == after_str);
+    if !equal {
+        report(path, before_str, after_str);
+    }
+}
+
+/// A single changed value inside a component or resource, as part of a [`WorldDiff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueChange {
+    /// The entity owning the component, or [`None`] if the value belongs to a resource.
+    pub entity: Option<Entity>,
+    /// The type path of the component or resource.
+    pub type_path: String,
+    /// The path of the changed field inside the value, e.g. `translation.x`.
+    /// This is empty if the value as a whole changed.
+    pub field: String,
+    /// The debug representation of the value before the change.
+    pub before: String,
+    /// The debug representation of the value after the change.
+    pub after: String,
+}
+
+/// The changes between a [`WorldSnapshot`] and the current world, created via [`App::diff_since`].
+///
+/// Entities are identified by their [`Entity`] id, so snapshots should be taken from the same app.
+/// The [`Display`](fmt::Display) implementation prints a readable change log.
+///
+/// ```
+/// use bevy_testing::p::*;
+///
+/// #[derive(Component, Reflect)]
+/// #[reflect(Component)]
+/// struct Health(u32);
+///
+/// let mut app = App::new();
+/// app.register_type::<Health>();
+/// let player = app.spawn(Health(10)).id();
+///
+/// let snapshot = app.snapshot();
+/// app.entity_mut(player).insert(Health(7));
+/// let enemy = app.spawn(Health(3)).id();
+///
+/// let diff = app.diff_since(&snapshot);
+/// assert_eq!(diff.spawned, vec![enemy]);
+/// assert_eq!(diff.changed[0].field, "0");
+/// assert_eq!(diff.changed[0].after, "7");
+/// println!("{diff}");
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WorldDiff {
+    /// Entities which didn't exist in the snapshot.
+    pub spawned: Vec<Entity>,
+    /// Entities which don't exist anymore.
+    pub despawned: Vec<Entity>,
+    /// Components which were added to existing entities, by type path.
+    pub added: Vec<(Entity, String)>,
+    /// Components which were removed from entities that still exist, by type path.
+    pub removed: Vec<(Entity, String)>,
+    /// Resources which were inserted, by type path.
+    pub resources_added: Vec<String>,
+    /// Resources which were removed, by type path.
+    pub resources_removed: Vec<String>,
+    /// Values of components and resources which changed.
+    pub changed: Vec<ValueChange>,
+    names: BTreeMap<Entity, String>,
+}
+
+impl WorldDiff {
+    /// Returns `true` if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.spawned.is_empty()
+            && self.despawned.is_empty()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.resources_added.is_empty()
+            && self.resources_removed.is_empty()
+            && self.changed.is_empty()
+    }
+
+    /// Checks if nothing changed, and prints the change log otherwise.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component, Reflect)]
+    /// #[reflect(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.register_type::<Health>();
+    /// app.spawn(Health(10));
+    ///
+    /// let snapshot = app.snapshot();
+    /// app.update_once();
+    /// app.diff_since(&snapshot).assert_empty();
+    /// ```
+    pub fn assert_empty(self) -> Self {
+        if !self.is_empty() {
+            print_failure("The world changed since the snapshot.");
+            eprint!("{self}");
+            panic!("assertion failed");
+        }
+
+        self
+    }
+
+    fn entity_label(&self, entity: Entity) -> String {
+        match self.names.get(&entity) {
+            Some(name) => format!("{entity} ({name:?})"),
+            None => entity.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for WorldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "{}", "no changes".bright_black());
+        }
+        for entity in &self.spawned {
+            writeln!(f, "{} {}", "spawned  ".green(), self.entity_label(*entity))?;
+        }
+        for entity in &self.despawned {
+            writeln!(f, "{} {}", "despawned".red(), self.entity_label(*entity))?;
+        }
+        for (entity, type_path) in &self.added {
+            writeln!(
+                f,
+                "{} {} {type_path}",
+                "added    ".green(),
+                self.entity_label(*entity)
+            )?;
+        }
+        for (entity, type_path) in &self.removed {
+            writeln!(
+                f,
+                "{} {} {type_path}",
+                "removed  ".red(),
+                self.entity_label(*entity)
+            )?;
+        }
+        for type_path in &self.resources_added {
+            writeln!(f, "{} resource {type_path}", "added    ".green())?;
+        }
+        for type_path in &self.resources_removed {
+            writeln!(f, "{} resource {type_path}", "removed  ".red())?;
+        }
+        for change in &self.changed {
+            let owner = match change.entity {
+                Some(entity) => self.entity_label(entity),
+                None => "resource".to_owned(),
+            };
+            let field = if change.field.is_empty() {
+                String::new()
+            } else {
+                format!(".{}", change.field)
+            };
+            writeln!(
+                f,
+                "{} {owner} {}{field}: {} {} {}",
+                "changed  ".yellow(),
+                change.type_path,
+                change.before,
+                "->".bright_black(),
+                change.after
+            )?;
+        }
+        Ok(())
+    }
+}
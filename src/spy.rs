@@ -0,0 +1,582 @@
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use std::{any::TypeId, collections::HashMap, mem};
+
+use bevy::{
+    app::{FixedMainScheduleOrder, MainScheduleOrder},
+    ecs::{
+        component::Tick,
+        schedule::{BoxedCondition, InternedScheduleLabel, NodeId, ScheduleGraph, ScheduleLabel},
+    },
+    utils::get_short_name,
+};
+use colored::Colorize;
+
+use crate::{print_failure, system_info};
+
+/// A resource which records the systems which ran during every frame.
+/// It gets installed via [`App::spy_systems`].
+#[derive(Resource)]
+pub(crate) struct SystemSpy {
+    /// The tick before the spy was installed.
+    start: Tick,
+    /// The tick at which the current frame started.
+    frame_start: Tick,
+    /// The tick at which every schedule was last polled.
+    polled: HashMap<InternedScheduleLabel, Tick>,
+    /// The runs of the current frame.
+    runs: Vec<SystemRun>,
+    pub(crate) frames: Vec<Vec<SystemRun>>,
+    /// The run conditions of every system, including those of its sets.
+    conditions: HashMap<(InternedScheduleLabel, TypeId), Gates>,
+}
+
+/// The names of the run conditions which gate a system.
+#[derive(Default)]
+struct Gates {
+    own: Vec<String>,
+    sets: Vec<SetGate>,
+}
+
+/// The names of the run conditions of a set, along with the systems in it.
+struct SetGate {
+    name: String,
+    conditions: Vec<String>,
+    systems: Vec<TypeId>,
+}
+
+/// A run of a system, as seen by the [`SystemSpy`].
+#[derive(Clone)]
+pub(crate) struct SystemRun {
+    type_id: TypeId,
+    name: String,
+    schedule: InternedScheduleLabel,
+    tick: u32,
+}
+
+/// Runs after [`Last`], once every schedule of the frame is done.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+struct SpySchedule;
+
+/// Runs after [`FixedLast`], once every fixed step is done.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+struct FixedSpySchedule;
+
+/// Runs at the start of every frame, before any schedule which changed gets built.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+struct ConditionSchedule;
+
+pub(crate) fn spy_systems(app: &mut App) {
+    if app.world().contains_resource::<SystemSpy>() {
+        return;
+    }
+    // the next system to run gets the current tick
+    let start = Tick::new(app.world().read_change_tick().get().wrapping_sub(1));
+    app.insert_resource(SystemSpy {
+        start,
+        frame_start: start,
+        polled: HashMap::new(),
+        runs: Vec::new(),
+        frames: Vec::new(),
+        conditions: HashMap::new(),
+    });
+    let mut order = app.world_mut().resource_mut::<MainScheduleOrder>();
+    order.insert_after(Last, SpySchedule);
+    order.insert_startup_before(PreStartup, ConditionSchedule);
+    order.insert_before(First, ConditionSchedule);
+    app.world_mut()
+        .resource_mut::<FixedMainScheduleOrder>()
+        .insert_after(FixedLast, FixedSpySchedule);
+    app.add_systems(SpySchedule, record_runs);
+    app.add_systems(FixedSpySchedule, poll_runs);
+    app.add_systems(ConditionSchedule, record_conditions);
+    record_conditions(app.world_mut());
+}
+
+/// Building a schedule moves the conditions out of its graph, so their names are collected
+/// from every schedule which wasn't built since systems were added to it.
+pub(crate) fn record_conditions(world: &mut World) {
+    let mut found = Vec::new();
+    for (_, schedule) in world.resource::<Schedules>().iter() {
+        let graph = schedule.graph();
+        for (id, system, conditions) in graph.systems() {
+            let mut gates = Gates {
+                own: conditions.iter().map(condition_name).collect(),
+                sets: Vec::new(),
+            };
+            for (set_id, set, conditions) in graph.system_sets() {
+                if conditions.is_empty() || !contains(graph, set_id, id) {
+                    continue;
+                }
+                gates.sets.push(SetGate {
+                    name: format!("{set:?}"),
+                    conditions: conditions.iter().map(condition_name).collect(),
+                    systems: graph
+                        .systems()
+                        .filter(|(other, _, _)| contains(graph, set_id, *other))
+                        .map(|(_, system, _)| system.type_id())
+                        .collect(),
+                });
+            }
+            found.push(((schedule.label(), system.type_id()), gates));
+        }
+    }
+    world.resource_mut::<SystemSpy>().conditions.extend(found);
+}
+
+fn condition_name(condition: &BoxedCondition) -> String {
+    get_short_name(&condition.name())
+}
+
+/// Returns `true` if the node is a descendant of the set.
+fn contains(graph: &ScheduleGraph, set: NodeId, node: NodeId) -> bool {
+    graph
+        .hierarchy()
+        .graph()
+        .neighbors(set)
+        .any(|child| child == node || contains(graph, child, node))
+}
+
+/// Checks if the system is part of the schedule, but didn't run during the last recorded frame
+/// because of a run condition, and names the condition.
+///
+/// Bevy doesn't expose the conditions of a built schedule, so their values are inferred from
+/// the systems which ran: a system only runs if all of its conditions and those of its sets are
+/// `true`, and the conditions of a set are `true` if any system in it ran.
+pub(crate) fn assert_gated(
+    world: &World,
+    type_id: TypeId,
+    name: &str,
+    label: InternedScheduleLabel,
+) {
+    let spy = world
+        .get_resource::<SystemSpy>()
+        .unwrap_or_else(|| panic!("systems are not recorded, call `App::spy_systems` first"));
+    let in_schedule =
+        world
+            .resource::<Schedules>()
+            .get(label)
+            .is_some_and(|schedule| match schedule.systems() {
+                Ok(mut systems) => systems.any(|(_, system)| system.type_id() == type_id),
+                Err(_) => schedule
+                    .graph()
+                    .systems()
+                    .any(|(_, system, _)| system.type_id() == type_id),
+            });
+    if !in_schedule {
+        print_failure(&format!(
+            "The system \"{name}\" is not part of the schedule \"{label:?}\"."
+        ));
+        panic!("assertion failed");
+    }
+    let Some(runs) = spy.frames.last() else {
+        print_failure(&format!("The system \"{name}\" wasn't gated."));
+        eprintln!("{}", "No frames were recorded.".bright_black());
+        panic!("assertion failed");
+    };
+    let ran = |type_id| {
+        runs.iter()
+            .any(|run| run.type_id == type_id && run.schedule == label)
+    };
+    let gates = spy
+        .conditions
+        .get(&(label, type_id))
+        .filter(|gates| !gates.own.is_empty() || !gates.sets.is_empty());
+    let Some(gates) = gates else {
+        print_failure(&format!(
+            "The system \"{name}\" has no known run conditions in \"{label:?}\"."
+        ));
+        eprintln!(
+            "{}",
+            "Conditions are only known if `App::spy_systems` was called before the schedule ran."
+                .bright_black()
+        );
+        panic!("assertion failed");
+    };
+
+    if ran(type_id) {
+        print_failure(&format!(
+            "The system \"{name}\" ran in \"{label:?}\" during the last frame."
+        ));
+        eprintln!("{}", "Conditions:".bright_black());
+        for (condition, _) in conditions(gates, true, |_| true) {
+            eprintln!("  {condition}: true");
+        }
+        panic!("assertion failed");
+    }
+
+    let candidates = conditions(gates, false, |set| {
+        set.systems.iter().any(|other| ran(*other))
+    })
+    .filter(|(_, known)| !known)
+    .map(|(condition, _)| condition)
+    .collect::<Vec<_>>();
+    match candidates.as_slice() {
+        [] => {
+            print_failure(&format!(
+                "The system \"{name}\" didn't run in \"{label:?}\" during the last frame, \
+                 but its run conditions were true."
+            ));
+            panic!("assertion failed");
+        }
+        [condition] => eprintln!(
+            "{}",
+            format!("The system \"{name}\" was gated by {condition}.").bright_black()
+        ),
+        _ => eprintln!(
+            "{}",
+            format!(
+                "The system \"{name}\" was gated by one of: {}.",
+                candidates.join(", ")
+            )
+            .bright_black()
+        ),
+    }
+}
+
+/// Returns the names of the conditions in the order they are evaluated, along with whether
+/// they are known to be `true`, which is the case if the system ran, or for the conditions
+/// of a set, if any system in it ran.
+fn conditions<'a>(
+    gates: &'a Gates,
+    own_ran: bool,
+    set_ran: impl Fn(&SetGate) -> bool + 'a,
+) -> impl Iterator<Item = (String, bool)> + 'a {
+    let sets = gates.sets.iter().flat_map(move |set| {
+        let known = set_ran(set);
+        set.conditions
+            .iter()
+            .map(move |condition| (format!("{condition} (of {})", set.name), known))
+    });
+    sets.chain(
+        gates
+            .own
+            .iter()
+            .map(move |condition| (condition.clone(), own_ran)),
+    )
+}
+
+/// Every system stores the tick of its last run, and every run increments the tick, so the
+/// systems which ran since a schedule was last polled can be found and ordered by it.
+///
+/// The schedules are polled after every fixed step and at the end of the frame, so a system
+/// which runs in several fixed steps of a frame has each run recorded.
+fn poll_runs(world: &mut World) {
+    let this_run = world.change_tick();
+    let apply_deferred = IntoSystem::system_type_id(&apply_deferred);
+    let spy = world.resource::<SystemSpy>();
+
+    let mut runs = Vec::new();
+    let mut polled = Vec::new();
+    for (_, schedule) in world.resource::<Schedules>().iter() {
+        let Ok(systems) = schedule.systems() else {
+            continue;
+        };
+        let last_run = spy
+            .polled
+            .get(&schedule.label())
+            .copied()
+            .unwrap_or(spy.start);
+        for (_, system) in systems {
+            let tick = system.get_last_run();
+            if system.type_id() == apply_deferred || !tick.is_newer_than(last_run, this_run) {
+                continue;
+            }
+            runs.push(SystemRun {
+                type_id: system.type_id(),
+                name: get_short_name(&system.name()),
+                schedule: schedule.label(),
+                tick: tick.get().wrapping_sub(spy.frame_start.get()),
+            });
+        }
+        polled.push(schedule.label());
+    }
+
+    let mut spy = world.resource_mut::<SystemSpy>();
+    spy.runs.extend(runs);
+    spy.polled
+        .extend(polled.into_iter().map(|label| (label, this_run)));
+}
+
+/// Collects the runs of the frame, once every other schedule is done.
+fn record_runs(world: &mut World) {
+    poll_runs(world);
+    let this_run = world.change_tick();
+    let mut spy = world.resource_mut::<SystemSpy>();
+    let mut runs = mem::take(&mut spy.runs);
+    runs.sort_by_key(|run| run.tick);
+    spy.frames.push(runs);
+    spy.frame_start = this_run;
+}
+
+/// A struct to perform tests on the systems which ran, which is created via [`App::systems`].
+///
+/// Every frame, the spy records which systems ran and in which order. A system which runs
+/// multiple times in one frame because of the fixed timestep, e.g. in `FixedUpdate`, is counted
+/// every time. The spy doesn't add anything to the schedules it observes, so a schedule which is
+/// run again by hand, e.g. via [`World::run_schedule`], only counts once per frame.
+///
+/// ```
+/// use bevy_testing::p::*;
+///
+/// #[derive(Resource)]
+/// struct Paused(bool);
+///
+/// fn physics() {}
+/// fn render() {}
+///
+/// let mut app = App::new();
+/// app.insert_resource(Paused(false));
+/// app.add_systems(Update, (
+///     physics.run_if(|paused: Res<Paused>| !paused.0),
+///     render.after(physics),
+/// ));
+/// app.spy_systems();
+///
+/// app.update_n_times(2);
+/// app.insert_resource(Paused(true));
+/// app.update_once();
+///
+/// app.systems()
+///     .ran(physics, 2)
+///     .ran(render, 3)
+///     .ran_before(physics, render);
+/// ```
+pub struct AssertSystems {
+    pub(crate) frames: Vec<Vec<SystemRun>>,
+    pub(crate) invert: bool,
+}
+
+impl AssertSystems {
+    /// Returns an inverted [`AssertSystems`].
+    /// When chaining methods,
+    /// the inverted state gets reset after every method.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// fn physics() {}
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(Update, physics);
+    /// app.spy_systems();
+    /// app.update_once();
+    ///
+    /// app.systems()
+    ///     .not().ran(physics, 2)
+    ///     .not().did_not_run(physics);
+    /// ```
+    #[allow(clippy::should_implement_trait)] // users should not need to import std::ops::Not
+    pub fn not(mut self) -> Self {
+        self.invert = !self.invert;
+        self
+    }
+
+    /// Checks if the system ran exactly `given` times, counting every run.
+    ///
+    /// This can be inverted via [`Self::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Resource, Default)]
+    /// struct Frame(u32);
+    ///
+    /// fn count(mut frame: ResMut<Frame>) {
+    ///     frame.0 += 1;
+    /// }
+    /// fn every_other_frame() {}
+    ///
+    /// let mut app = App::new();
+    /// app.init_resource::<Frame>();
+    /// app.add_systems(Update, (
+    ///     count,
+    ///     every_other_frame.run_if(|frame: Res<Frame>| frame.0 % 2 == 0),
+    /// ).chain());
+    /// app.spy_systems();
+    /// app.update_n_times(4);
+    ///
+    /// app.systems()
+    ///     .ran(count, 4)
+    ///     .ran(every_other_frame, 2);
+    /// ```
+    pub fn ran<I, O, M>(self, system: impl IntoSystem<I, O, M>, given: usize) -> Self {
+        let (type_id, name) = system_info(system);
+        let found = self.count(type_id);
+        if self.invert {
+            if found == given {
+                self.fail(&format!("The system \"{name}\" ran {given} times."));
+            }
+            return self.reset_invert();
+        }
+
+        if found != given {
+            self.fail(&format!(
+                "The system \"{name}\" ran {found} times instead of {given}."
+            ));
+        }
+
+        self
+    }
+
+    /// Checks if the system never ran, e.g. because its run condition was never met.
+    ///
+    /// This can be inverted via [`Self::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// fn game_over() {}
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(Update, game_over.run_if(|| false));
+    /// app.spy_systems();
+    /// app.update_n_times(3);
+    ///
+    /// app.systems()
+    ///     .did_not_run(game_over);
+    /// ```
+    pub fn did_not_run<I, O, M>(self, system: impl IntoSystem<I, O, M>) -> Self {
+        let (type_id, name) = system_info(system);
+        let found = self.count(type_id);
+        if self.invert {
+            if found == 0 {
+                self.fail(&format!("The system \"{name}\" never ran."));
+            }
+            return self.reset_invert();
+        }
+
+        if found != 0 {
+            self.fail(&format!("The system \"{name}\" ran {found} times."));
+        }
+
+        self
+    }
+
+    /// Checks if `a` ran before `b` during every frame in which both of them ran,
+    /// and that there is at least one such frame.
+    ///
+    /// This can be inverted via [`Self::not`].
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// fn input() {}
+    /// fn movement() {}
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(PreUpdate, input);
+    /// app.add_systems(Update, movement);
+    /// app.spy_systems();
+    /// app.update_n_times(2);
+    ///
+    /// app.systems()
+    ///     .ran_before(input, movement)
+    ///     .not().ran_before(movement, input);
+    /// ```
+    pub fn ran_before<I1, O1, M1, I2, O2, M2>(
+        self,
+        a: impl IntoSystem<I1, O1, M1>,
+        b: impl IntoSystem<I2, O2, M2>,
+    ) -> Self {
+        let (a, a_name) = system_info(a);
+        let (b, b_name) = system_info(b);
+        let mut together = self.frames.iter().filter_map(|runs| {
+            let a = runs.iter().position(|run| run.type_id == a)?;
+            let b = runs.iter().position(|run| run.type_id == b)?;
+            Some(a < b)
+        });
+        let mut any = false;
+        let before = together.all(|before| {
+            any = true;
+            before
+        }) && any;
+
+        if self.invert {
+            if before {
+                self.fail(&format!("The system \"{a_name}\" ran before \"{b_name}\"."));
+            }
+            return self.reset_invert();
+        }
+
+        if !any {
+            self.fail(&format!(
+                "The systems \"{a_name}\" and \"{b_name}\" never ran during the same frame."
+            ));
+        }
+        if !before {
+            self.fail(&format!(
+                "The system \"{a_name}\" didn't run before \"{b_name}\"."
+            ));
+        }
+
+        self
+    }
+
+    /// Prints the systems which ran during every frame to stderr,
+    /// which can be useful while writing a test.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// fn physics() {}
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(Update, physics);
+    /// app.spy_systems();
+    /// app.update_n_times(2);
+    ///
+    /// app.systems().print();
+    /// ```
+    pub fn print(self) -> Self {
+        eprintln!("{}", self.runs());
+        self
+    }
+
+    fn count(&self, type_id: TypeId) -> usize {
+        self.frames
+            .iter()
+            .flatten()
+            .filter(|run| run.type_id == type_id)
+            .count()
+    }
+
+    fn fail(&self, message: &str) -> ! {
+        print_failure(message);
+        eprintln!("{}", "Runs:".bright_black());
+        eprintln!("{}", self.runs());
+        panic!("assertion failed");
+    }
+
+    /// Renders the runs, one frame per line.
+    fn runs(&self) -> String {
+        if self.frames.is_empty() {
+            return "No frames were recorded.".bright_black().to_string();
+        }
+        self.frames
+            .iter()
+            .enumerate()
+            .map(|(i, runs)| {
+                let systems = runs
+                    .iter()
+                    .map(|run| {
+                        format!(
+                            "{} {}",
+                            run.name,
+                            format!("({:?})", run.schedule).bright_black()
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{} {systems}", format!("Frame {}:", i + 1).bright_black())
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn reset_invert(mut self) -> Self {
+        self.invert = false;
+        self
+    }
+}
@@ -0,0 +1,431 @@
+#[allow(unused_imports)] // used in doc
+use super::p::*;
+
+use std::{
+    env,
+    fmt::Write as _,
+    fs,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use bevy::{
+    ecs::query::{QueryFilter, ReadOnlyQueryData},
+    reflect::ReflectRef,
+};
+use colored::Colorize;
+
+/// Query data whose items can be written into a [`Trace`].
+///
+/// This is implemented for [`Entity`], for `&T` where `T` is a [`Component`] implementing
+/// [`Reflect`], and for tuples of those. Every field of a component becomes its own column,
+/// named after the component and the path of the field, e.g. `Transform.translation.x`.
+pub trait TraceData: ReadOnlyQueryData {
+    /// Appends the columns of the given item to `columns`.
+    fn columns(item: &Self::Item<'_>, columns: &mut Vec<(String, TraceValue)>);
+}
+
+/// The value of a column in a [`Trace`], which decides how it is written to JSON.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TraceValue {
+    /// A finite number, written as a JSON number.
+    Number(String),
+    /// A boolean, written as a JSON boolean.
+    Bool(bool),
+    /// Any other value, written as a JSON string.
+    Text(String),
+}
+
+impl TraceValue {
+    fn text(&self) -> String {
+        match self {
+            Self::Number(text) | Self::Text(text) => text.clone(),
+            Self::Bool(value) => value.to_string(),
+        }
+    }
+
+    fn json(&self) -> String {
+        match self {
+            Self::Number(text) => text.clone(),
+            Self::Bool(value) => value.to_string(),
+            Self::Text(text) => json_string(text),
+        }
+    }
+}
+
+impl TraceData for Entity {
+    fn columns(item: &Entity, columns: &mut Vec<(String, TraceValue)>) {
+        columns.push(("Entity".to_owned(), TraceValue::Text(entity_id(*item))));
+    }
+}
+
+impl<T: Component + Reflect> TraceData for &T {
+    fn columns(item: &&T, columns: &mut Vec<(String, TraceValue)>) {
+        let value = item.as_reflect();
+        flatten(value.reflect_short_type_path().to_owned(), value, columns);
+    }
+}
+
+macro_rules! impl_trace_data {
+    ($($name:ident),*) => {
+        impl<$($name: TraceData),*> TraceData for ($($name,)*) {
+            #[allow(non_snake_case)]
+            fn columns(item: &Self::Item<'_>, columns: &mut Vec<(String, TraceValue)>) {
+                let ($($name,)*) = item;
+                $($name::columns($name, columns);)*
+            }
+        }
+    };
+}
+
+impl_trace_data!(A);
+impl_trace_data!(A, B);
+impl_trace_data!(A, B, C);
+impl_trace_data!(A, B, C, D);
+impl_trace_data!(A, B, C, D, E);
+impl_trace_data!(A, B, C, D, E, F);
+
+fn entity_id(entity: Entity) -> String {
+    format!("{}v{}", entity.index(), entity.generation())
+}
+
+/// Splits a value into one column per leaf field.
+fn flatten(path: String, value: &dyn Reflect, columns: &mut Vec<(String, TraceValue)>) {
+    let field = |name: &str| format!("{path}.{name}");
+
+    match value.reflect_ref() {
+        ReflectRef::Struct(value) => {
+            for (i, item) in value.iter_fields().enumerate() {
+                flatten(field(value.name_at(i).unwrap()), item, columns);
+            }
+        }
+        ReflectRef::TupleStruct(value) => {
+            for (i, item) in value.iter_fields().enumerate() {
+                flatten(field(&i.to_string()), item, columns);
+            }
+        }
+        ReflectRef::Tuple(value) => {
+            for (i, item) in value.iter_fields().enumerate() {
+                flatten(field(&i.to_string()), item, columns);
+            }
+        }
+        ReflectRef::List(value) => {
+            for (i, item) in value.iter().enumerate() {
+                flatten(format!("{path}[{i}]"), item, columns);
+            }
+        }
+        ReflectRef::Array(value) => {
+            for (i, item) in value.iter().enumerate() {
+                flatten(format!("{path}[{i}]"), item, columns);
+            }
+        }
+        ReflectRef::Enum(value) if value.field_len() == 0 => {
+            columns.push((path, TraceValue::Text(value.variant_name().to_owned())));
+        }
+        ReflectRef::Value(value) => columns.push((path, primitive(value))),
+        _ => columns.push((path, TraceValue::Text(format!("{value:?}")))),
+    }
+}
+
+/// Converts a reflected value, keeping its kind if it is a number or a boolean.
+fn primitive(value: &dyn Reflect) -> TraceValue {
+    let any = value.as_any();
+    if let Some(value) = any.downcast_ref::<bool>() {
+        return TraceValue::Bool(*value);
+    }
+    if let Some(text) = any.downcast_ref::<String>() {
+        return TraceValue::Text(text.clone());
+    }
+    if let Some(entity) = any.downcast_ref::<Entity>() {
+        return TraceValue::Text(entity_id(*entity));
+    }
+
+    let float = any
+        .downcast_ref::<f32>()
+        .map(|float| f64::from(*float))
+        .or_else(|| any.downcast_ref::<f64>().copied());
+    let is_integer = any.is::<i8>()
+        || any.is::<i16>()
+        || any.is::<i32>()
+        || any.is::<i64>()
+        || any.is::<i128>()
+        || any.is::<isize>()
+        || any.is::<u8>()
+        || any.is::<u16>()
+        || any.is::<u32>()
+        || any.is::<u64>()
+        || any.is::<u128>()
+        || any.is::<usize>();
+    // JSON has no representation of NaN or infinity
+    if is_integer || float.is_some_and(f64::is_finite) {
+        TraceValue::Number(format!("{value:?}"))
+    } else {
+        TraceValue::Text(format!("{value:?}"))
+    }
+}
+
+/// A builder which samples a query while updating the app, created via [`App::trace`].
+///
+/// Call [`Tracer::frames`] to run the app and get the resulting [`Trace`].
+pub struct Tracer<'a, D: TraceData, F: QueryFilter> {
+    pub(crate) app: &'a mut App,
+    pub(crate) dump_on_panic: bool,
+    pub(crate) marker: PhantomData<(D, F)>,
+}
+
+impl<'a, D: TraceData, F: QueryFilter> Tracer<'a, D, F> {
+    /// Makes the trace write itself to `target/bevy_testing/<test name>.csv` if the thread
+    /// panics, either while the app is updated by [`Tracer::frames`] or while the resulting
+    /// [`Trace`] is still alive. Keep the trace in a variable until the end of the test.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component, Debug, PartialEq, Reflect)]
+    /// struct Height(f32);
+    ///
+    /// let mut app = App::new();
+    /// app.spawn(Height(10.0));
+    ///
+    /// let _trace = app.trace::<&Height, ()>().dump_on_panic().frames(60);
+    /// app.query::<&Height>()
+    ///     .matches(vec![&Height(10.0)]);
+    /// ```
+    pub fn dump_on_panic(mut self) -> Self {
+        self.dump_on_panic = true;
+        self
+    }
+
+    /// Samples the query, then updates the app `amount` times, sampling the query after every update.
+    /// Frame `0` is the state before the first update.
+    ///
+    /// ```
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component, Reflect)]
+    /// struct Height(f32);
+    ///
+    /// fn fall(mut query: Query<&mut Height>) {
+    ///     for mut height in &mut query {
+    ///         height.0 -= 1.0;
+    ///     }
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.add_systems(Update, fall);
+    /// app.spawn(Height(10.0));
+    ///
+    /// let trace = app.trace::<&Height, ()>().frames(3);
+    /// assert_eq!(trace.len(), 4);
+    /// ```
+    pub fn frames(mut self, amount: u32) -> Trace {
+        let mut trace = Trace {
+            header: Vec::new(),
+            rows: Vec::new(),
+            dump_on_panic: self.dump_on_panic,
+        };
+        self.sample(&mut trace, 0);
+        for frame in 1..=amount {
+            self.app.update_once();
+            self.sample(&mut trace, frame);
+        }
+        trace
+    }
+
+    fn sample(&mut self, trace: &mut Trace, frame: u32) {
+        let world = self.app.world_mut();
+        let mut query = world.query_filtered::<(Entity, D), F>();
+        for (entity, item) in query.iter(world) {
+            let mut columns = Vec::new();
+            D::columns(&item, &mut columns);
+            trace.push(frame, entity, columns);
+        }
+    }
+}
+
+/// The per-frame values of a query, created via [`Tracer::frames`].
+///
+/// Every row contains the frame, the entity and the columns of the query item.
+/// Use [`Trace::to_csv`] or [`Trace::to_json_lines`] to write it to a file.
+pub struct Trace {
+    header: Vec<String>,
+    rows: Vec<(u32, Entity, Vec<Option<TraceValue>>)>,
+    dump_on_panic: bool,
+}
+
+impl Trace {
+    /// Returns the amount of rows, which is one per frame and entity.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Returns `true` if the query didn't match any entity on any frame.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Writes the trace as CSV, with a header row and one row per frame and entity.
+    ///
+    /// Panics if the file can't be written.
+    ///
+    /// ```no_run
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Player;
+    ///
+    /// let mut app = App::new();
+    /// app.spawn((Player, Transform::default()));
+    ///
+    /// app.trace::<&Transform, With<Player>>()
+    ///     .frames(300)
+    ///     .to_csv("target/player.csv");
+    /// ```
+    pub fn to_csv(self, path: impl AsRef<Path>) -> Self {
+        write_file(path.as_ref(), &self.csv());
+        self
+    }
+
+    /// Writes the trace as JSON lines, with one object per frame and entity.
+    ///
+    /// Panics if the file can't be written.
+    ///
+    /// ```no_run
+    /// use bevy_testing::p::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Player;
+    ///
+    /// let mut app = App::new();
+    /// app.spawn((Player, Transform::default()));
+    ///
+    /// app.trace::<&Transform, With<Player>>()
+    ///     .frames(300)
+    ///     .to_json_lines("target/player.jsonl");
+    /// ```
+    pub fn to_json_lines(self, path: impl AsRef<Path>) -> Self {
+        write_file(path.as_ref(), &self.json_lines());
+        self
+    }
+
+    fn push(&mut self, frame: u32, entity: Entity, columns: Vec<(String, TraceValue)>) {
+        let mut row = vec![None; self.header.len()];
+        for (name, value) in columns {
+            let index = match self.header.iter().position(|column| *column == name) {
+                Some(index) => index,
+                None => {
+                    self.header.push(name);
+                    row.push(None);
+                    self.header.len() - 1
+                }
+            };
+            row[index] = Some(value);
+        }
+        self.rows.push((frame, entity, row));
+    }
+
+    fn csv(&self) -> String {
+        let mut out = String::from("frame,entity");
+        for column in &self.header {
+            write!(out, ",{}", csv_escape(column)).unwrap();
+        }
+        out.push('\n');
+        for (frame, entity, row) in &self.rows {
+            write!(out, "{frame},{}", entity_id(*entity)).unwrap();
+            for index in 0..self.header.len() {
+                let value = row.get(index).and_then(|value| value.as_ref());
+                let value = value.map(TraceValue::text).unwrap_or_default();
+                write!(out, ",{}", csv_escape(&value)).unwrap();
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    fn json_lines(&self) -> String {
+        let mut out = String::new();
+        for (frame, entity, row) in &self.rows {
+            write!(
+                out,
+                "{{\"frame\":{frame},\"entity\":\"{}\"",
+                entity_id(*entity)
+            )
+            .unwrap();
+            for (column, value) in self.header.iter().zip(row) {
+                if let Some(value) = value {
+                    write!(out, ",{}:{}", json_string(column), value.json()).unwrap();
+                }
+            }
+            out += "}\n";
+        }
+        out
+    }
+}
+
+impl Drop for Trace {
+    fn drop(&mut self) {
+        if !self.dump_on_panic || !thread::panicking() {
+            return;
+        }
+
+        let name = thread::current()
+            .name()
+            .unwrap_or("trace")
+            .replace(|c: char| !c.is_ascii_alphanumeric() && c != '_', "_");
+        let path = dump_dir().join(format!("{name}.csv"));
+        // a second panic would abort, so failing to write is only reported
+        match fs::create_dir_all(dump_dir()).and_then(|_| fs::write(&path, self.csv())) {
+            Ok(()) => eprintln!(
+                "{} {}",
+                "Trace written to".bright_black(),
+                path.display().to_string().bright_black()
+            ),
+            Err(err) => eprintln!("failed to write trace {}: {err}", path.display()),
+        }
+    }
+}
+
+fn dump_dir() -> PathBuf {
+    let target = env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            env::var_os("CARGO_MANIFEST_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_default()
+                .join("target")
+        });
+    target.join("bevy_testing")
+}
+
+fn write_file(path: &Path, content: &str) {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)
+            .unwrap_or_else(|err| panic!("failed to create directory {}: {err}", dir.display()));
+    }
+    fs::write(path, content)
+        .unwrap_or_else(|err| panic!("failed to write trace {}: {err}", path.display()));
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+pub(crate) fn json_string(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out += "\\\"",
+            '\\' => out += "\\\\",
+            '\n' => out += "\\n",
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,56 @@
+//! Runs a passing and a failing `#[bevy_test]` in the harness and checks the reports.
+
+use std::{env, fs};
+
+use bevy_testing::bevy_test;
+use bevy_testing::harness::{self, Arguments};
+use bevy_testing::p::*;
+
+#[derive(Component, Debug, PartialEq)]
+struct Health(u32);
+
+#[bevy_test(frames = 3)]
+fn passes(app: &mut App) {
+    app.spawn(Health(10));
+    app.query::<&Health>().matches(vec![&Health(10)]);
+}
+
+#[bevy_test(frames = 2)]
+fn fails(app: &mut App) {
+    app.spawn(Health(10));
+    app.update_once();
+    app.query::<&Health>().matches(vec![&Health(5)]);
+}
+
+fn main() {
+    let args = Arguments::from_args();
+    if args.list {
+        harness::run(&args).exit();
+    }
+
+    let dir = env::temp_dir().join(format!("bevy_testing_harness_{}", std::process::id()));
+    let (json, junit) = (dir.join("report.json"), dir.join("report.xml"));
+    env::set_var("BEVY_TESTING_JSON", &json);
+    env::set_var("BEVY_TESTING_JUNIT", &junit);
+
+    eprintln!("The test \"fails\" is expected to fail.");
+    let conclusion = harness::run(&Arguments::default());
+    assert_eq!((conclusion.num_passed, conclusion.num_failed), (1, 1));
+
+    let json = fs::read_to_string(json).unwrap();
+    assert!(json.contains(r#"{"name":"fails","passed":false,"frames":3,"#));
+    assert!(json.contains(r#""message":"assertion failed""#));
+    assert!(json.contains(r#""details":["One of the given bundles wasn't found in the query.""#));
+    assert!(json.contains(r#"{"name":"passes","passed":true,"frames":3,"#));
+
+    let junit = fs::read_to_string(junit).unwrap();
+    assert!(junit.contains(r#"<testsuites tests="2" failures="1""#));
+    assert!(junit.contains(r#"<testcase classname="" name="fails""#));
+    assert!(junit.contains(r#"<property name="frames" value="3"/>"#));
+    assert!(junit.contains(
+        r#"<failure message="assertion failed">One of the given bundles wasn&apos;t found"#
+    ));
+
+    fs::remove_dir_all(dir).unwrap();
+    eprintln!("The reports match the results.");
+}